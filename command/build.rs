@@ -0,0 +1,5 @@
+// Embedded migrations are read at compile time by `sqlx::migrate!`, so the
+// binary has to be rebuilt whenever one of them changes.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+}
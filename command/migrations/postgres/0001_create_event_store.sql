@@ -0,0 +1,41 @@
+CREATE TABLE IF NOT EXISTS events (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    sequence TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    event_type TEXT NOT NULL,
+    event_version TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    metadata JSONB NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (aggregate_id, version)
+);
+
+CREATE INDEX IF NOT EXISTS events_aggregate_id_sequence ON events (aggregate_id, sequence);
+
+CREATE TABLE IF NOT EXISTS snapshots (
+    snapshot_id TEXT NOT NULL PRIMARY KEY,
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    last_sequence TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS snapshots_aggregate_id_snapshot_id ON snapshots (aggregate_id, snapshot_id);
+
+CREATE TABLE IF NOT EXISTS outbox_events (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    sequence TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    event_type TEXT NOT NULL,
+    event_version TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    metadata JSONB NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (aggregate_id, version)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS outbox_events_sequence ON outbox_events (sequence);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS prescription_view (
+    id TEXT NOT NULL PRIMARY KEY,
+    version BIGINT NOT NULL,
+    payload JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS prescription_view_patient_id ON prescription_view ((payload ->> 'patient_id'));
+
+CREATE INDEX IF NOT EXISTS prescription_view_medication_id ON prescription_view ((payload ->> 'medication_id'));
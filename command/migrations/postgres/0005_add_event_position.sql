@@ -0,0 +1,16 @@
+ALTER TABLE events ADD COLUMN IF NOT EXISTS position BIGINT;
+
+UPDATE events SET position = numbered.position
+FROM (
+    SELECT aggregate_id, version, ROW_NUMBER() OVER (ORDER BY timestamp, aggregate_id, version) AS position
+    FROM events
+) AS numbered
+WHERE events.aggregate_id = numbered.aggregate_id AND events.version = numbered.version;
+
+CREATE UNIQUE INDEX IF NOT EXISTS events_position ON events (position);
+
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS position BIGINT;
+
+UPDATE outbox_events SET position = events.position
+FROM events
+WHERE events.aggregate_id = outbox_events.aggregate_id AND events.version = outbox_events.version;
@@ -0,0 +1,4 @@
+CREATE TABLE IF NOT EXISTS subscription_checkpoints (
+    subscription TEXT NOT NULL PRIMARY KEY,
+    position BIGINT NOT NULL
+);
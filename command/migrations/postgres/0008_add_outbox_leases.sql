@@ -0,0 +1,5 @@
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS lease_owner TEXT;
+
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS lease_expires_at TIMESTAMPTZ;
+
+CREATE INDEX IF NOT EXISTS outbox_events_aggregate_type_position ON outbox_events (aggregate_type, position);
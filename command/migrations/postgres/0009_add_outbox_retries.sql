@@ -0,0 +1,23 @@
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS attempts BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS last_error TEXT;
+
+ALTER TABLE outbox_events ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ;
+
+CREATE TABLE IF NOT EXISTS outbox_dead_letters (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    sequence TEXT NOT NULL PRIMARY KEY,
+    version BIGINT NOT NULL,
+    event_type TEXT NOT NULL,
+    event_version TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    metadata JSONB NOT NULL,
+    timestamp TIMESTAMPTZ NOT NULL,
+    position BIGINT,
+    attempts BIGINT NOT NULL,
+    last_error TEXT NOT NULL,
+    dead_lettered_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS outbox_dead_letters_aggregate_type_position ON outbox_dead_letters (aggregate_type, position);
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS inbox_messages (
+    consumer TEXT NOT NULL,
+    message_id TEXT NOT NULL,
+    processed_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (consumer, message_id)
+);
+
+CREATE INDEX IF NOT EXISTS inbox_messages_processed_at ON inbox_messages (processed_at);
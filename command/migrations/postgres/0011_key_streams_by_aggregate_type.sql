@@ -0,0 +1,7 @@
+ALTER TABLE events DROP CONSTRAINT IF EXISTS events_pkey;
+
+ALTER TABLE events ADD PRIMARY KEY (aggregate_type, aggregate_id, version);
+
+ALTER TABLE outbox_events DROP CONSTRAINT IF EXISTS outbox_events_pkey;
+
+ALTER TABLE outbox_events ADD PRIMARY KEY (aggregate_type, aggregate_id, version);
@@ -0,0 +1 @@
+DELETE FROM snapshots WHERE aggregate_type = 'Prescription' AND NOT payload ? 'address';
@@ -0,0 +1,5 @@
+CREATE INDEX IF NOT EXISTS events_aggregate_type_aggregate_id ON events (aggregate_type, aggregate_id);
+
+CREATE INDEX IF NOT EXISTS snapshots_aggregate_type_aggregate_id ON snapshots (aggregate_type, aggregate_id);
+
+CREATE INDEX IF NOT EXISTS outbox_events_aggregate_type ON outbox_events (aggregate_type);
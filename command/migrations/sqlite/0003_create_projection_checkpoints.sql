@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS projection_checkpoints (
+    projection TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    PRIMARY KEY (projection, aggregate_id)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS prescription_view (
+    id TEXT NOT NULL PRIMARY KEY,
+    version INTEGER NOT NULL,
+    payload JSON NOT NULL,
+    updated_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS prescription_view_patient_id ON prescription_view (json_extract(payload, '$.patient_id'));
+
+CREATE INDEX IF NOT EXISTS prescription_view_medication_id ON prescription_view (json_extract(payload, '$.medication_id'));
@@ -0,0 +1,12 @@
+ALTER TABLE events ADD COLUMN position INTEGER;
+
+UPDATE events SET position = (SELECT COUNT(*) FROM events AS earlier WHERE earlier.rowid <= events.rowid);
+
+CREATE UNIQUE INDEX IF NOT EXISTS events_position ON events (position);
+
+ALTER TABLE outbox_events ADD COLUMN position INTEGER;
+
+UPDATE outbox_events SET position = (
+    SELECT events.position FROM events
+    WHERE events.aggregate_id = outbox_events.aggregate_id AND events.version = outbox_events.version
+);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS events_aggregate_id_sequence;
+
+ALTER TABLE snapshots DROP COLUMN last_sequence;
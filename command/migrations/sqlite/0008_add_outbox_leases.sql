@@ -0,0 +1,5 @@
+ALTER TABLE outbox_events ADD COLUMN lease_owner TEXT;
+
+ALTER TABLE outbox_events ADD COLUMN lease_expires_at DATETIME;
+
+CREATE INDEX IF NOT EXISTS outbox_events_aggregate_type_position ON outbox_events (aggregate_type, position);
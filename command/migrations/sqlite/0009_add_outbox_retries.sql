@@ -0,0 +1,23 @@
+ALTER TABLE outbox_events ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE outbox_events ADD COLUMN last_error TEXT;
+
+ALTER TABLE outbox_events ADD COLUMN next_attempt_at DATETIME;
+
+CREATE TABLE IF NOT EXISTS outbox_dead_letters (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    sequence TEXT NOT NULL PRIMARY KEY,
+    version INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    event_version TEXT NOT NULL,
+    payload JSON NOT NULL,
+    metadata JSON NOT NULL,
+    timestamp DATETIME NOT NULL,
+    position INTEGER,
+    attempts INTEGER NOT NULL,
+    last_error TEXT NOT NULL,
+    dead_lettered_at DATETIME NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS outbox_dead_letters_aggregate_type_position ON outbox_dead_letters (aggregate_type, position);
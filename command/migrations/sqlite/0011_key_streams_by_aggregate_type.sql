@@ -0,0 +1,68 @@
+CREATE TABLE events_rekeyed (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    sequence TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    event_version TEXT NOT NULL,
+    payload JSON NOT NULL,
+    metadata JSON NOT NULL,
+    timestamp DATETIME NOT NULL,
+    position INTEGER,
+    PRIMARY KEY (aggregate_type, aggregate_id, version)
+);
+
+INSERT INTO events_rekeyed (
+    aggregate_type, aggregate_id, sequence, version, event_type, event_version, payload, metadata,
+    timestamp, position
+)
+SELECT
+    aggregate_type, aggregate_id, sequence, version, event_type, event_version, payload, metadata,
+    timestamp, position
+FROM events;
+
+DROP TABLE events;
+
+ALTER TABLE events_rekeyed RENAME TO events;
+
+CREATE INDEX IF NOT EXISTS events_aggregate_type_aggregate_id ON events (aggregate_type, aggregate_id);
+
+CREATE UNIQUE INDEX IF NOT EXISTS events_position ON events (position);
+
+CREATE TABLE outbox_events_rekeyed (
+    aggregate_type TEXT NOT NULL,
+    aggregate_id TEXT NOT NULL,
+    sequence TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    event_type TEXT NOT NULL,
+    event_version TEXT NOT NULL,
+    payload JSON NOT NULL,
+    metadata JSON NOT NULL,
+    timestamp DATETIME NOT NULL,
+    position INTEGER,
+    lease_owner TEXT,
+    lease_expires_at DATETIME,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    next_attempt_at DATETIME,
+    PRIMARY KEY (aggregate_type, aggregate_id, version)
+);
+
+INSERT INTO outbox_events_rekeyed (
+    aggregate_type, aggregate_id, sequence, version, event_type, event_version, payload, metadata,
+    timestamp, position, lease_owner, lease_expires_at, attempts, last_error, next_attempt_at
+)
+SELECT
+    aggregate_type, aggregate_id, sequence, version, event_type, event_version, payload, metadata,
+    timestamp, position, lease_owner, lease_expires_at, attempts, last_error, next_attempt_at
+FROM outbox_events;
+
+DROP TABLE outbox_events;
+
+ALTER TABLE outbox_events_rekeyed RENAME TO outbox_events;
+
+CREATE UNIQUE INDEX IF NOT EXISTS outbox_events_sequence ON outbox_events (sequence);
+
+CREATE INDEX IF NOT EXISTS outbox_events_aggregate_type ON outbox_events (aggregate_type);
+
+CREATE INDEX IF NOT EXISTS outbox_events_aggregate_type_position ON outbox_events (aggregate_type, position);
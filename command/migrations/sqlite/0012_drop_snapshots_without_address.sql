@@ -0,0 +1 @@
+DELETE FROM snapshots WHERE aggregate_type = 'Prescription' AND json_type(payload, '$.address') IS NULL;
@@ -0,0 +1,22 @@
+-- 0005 numbered existing events by rowid while Postgres numbered them by
+-- (timestamp, aggregate_id, version). Renumber in the Postgres order so both
+-- backends agree; 0005 itself stays as applied, since sqlx checksums it.
+-- Positions go through negative values to keep events_position unique.
+UPDATE events SET position = -(
+    SELECT COUNT(*) FROM events AS earlier
+    WHERE (earlier.timestamp, earlier.aggregate_id, earlier.version, earlier.aggregate_type)
+        <= (events.timestamp, events.aggregate_id, events.version, events.aggregate_type)
+);
+
+UPDATE events SET position = -position;
+
+UPDATE outbox_events SET position = (
+    SELECT events.position FROM events
+    WHERE events.aggregate_type = outbox_events.aggregate_type
+        AND events.aggregate_id = outbox_events.aggregate_id
+        AND events.version = outbox_events.version
+);
+
+-- Stored subscription positions refer to the old numbering, so subscriptions
+-- start over and see every event again.
+DELETE FROM subscription_checkpoints;
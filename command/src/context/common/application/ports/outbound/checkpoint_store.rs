@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait CheckpointStore {
+    // The version of the last event of `aggregate_id` that `projection` has
+    // handled, 0 when it has not seen the aggregate yet
+    async fn load_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+    ) -> Result<i64, anyhow::Error>;
+    async fn save_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+        version: i64,
+    ) -> Result<(), anyhow::Error>;
+    // Forgets everything `projection` has handled so it can be replayed
+    async fn reset_checkpoints(&self, projection: &str) -> Result<(), anyhow::Error>;
+    // The global position up to which `subscription` has processed events, 0
+    // when it has not started yet
+    async fn load_position(&self, subscription: &str) -> Result<i64, anyhow::Error>;
+    async fn save_position(&self, subscription: &str, position: i64) -> Result<(), anyhow::Error>;
+}
@@ -1,10 +1,24 @@
-use std::sync::Arc;
+use std::pin::Pin;
 
 use async_trait::async_trait;
 use futures::Stream;
 
+use crate::context::common::domain::entity::filter::EventFilter;
+
 #[async_trait]
 pub trait EventBus<IE, OE> {
     async fn send_event(&self, event: IE) -> Result<(), anyhow::Error>;
-    async fn receive_events(&self) -> Box<dyn Stream<Item = OE>>;
+    // Subscribes to every event sent from now on
+    async fn receive_events(&self) -> Pin<Box<dyn Stream<Item = OE> + Send>>;
+    // Joins the consumer group `group`, whose members share one subscription:
+    // each event sent from now on reaches one member of the group
+    async fn receive_group(&self, group: &str) -> Pin<Box<dyn Stream<Item = OE> + Send>>;
+    // Subscribes to the events sent from now on that `filter` lets through.
+    // With a `group`, joins it: each event goes to one of the members whose
+    // filter lets it through
+    async fn receive_filtered(
+        &self,
+        filter: EventFilter,
+        group: Option<&str>,
+    ) -> Pin<Box<dyn Stream<Item = OE> + Send>>;
 }
@@ -1,29 +1,118 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
 
 use super::event_bus::EventBus;
+use crate::context::common::domain::entity::event::{
+    AggregateSnapshot, DeadLetter, EventEnvelope, OutboxDelivery,
+};
 
 #[async_trait]
 pub trait EventRepository<IE, OE, IS, OS> {
-    async fn store_events(&self, events: Vec<IE>) -> Result<(), anyhow::Error>;
+    // Appends `events` to a single aggregate stream. Fails with
+    // `EventStoreError::ConcurrencyConflict` if the stream is no longer at
+    // `expected_version` (0 for a stream that should not exist yet)
+    async fn store_events(
+        &self,
+        events: Vec<IE>,
+        expected_version: i64,
+    ) -> Result<(), anyhow::Error>;
+    // Events of a single aggregate stream in version order, starting after
+    // version `after` when it is given
     async fn retrieve_events(
         &self,
         aggregate_id: String,
-        after: Option<String>,
+        after: Option<i64>,
     ) -> Result<Vec<OE>, anyhow::Error>;
+    // Events of every aggregate of the type with a position greater than
+    // `after`, in position order and at most `limit` of them
+    async fn retrieve_all_events(&self, after: i64, limit: i64) -> Result<Vec<OE>, anyhow::Error>;
     async fn store_snapshot(&self, snapshot: IS) -> Result<(), anyhow::Error>;
     async fn retrieve_latest_snapshot(
         &self,
         aggregate_id: String,
     ) -> Result<Option<OS>, anyhow::Error>;
+    // Deletes every snapshot except the newest `keep_last` of each aggregate,
+    // sparing those taken at or after `taken_before` when it is given
+    async fn prune_snapshots(
+        &self,
+        keep_last: i64,
+        taken_before: Option<DateTime<Utc>>,
+    ) -> Result<u64, anyhow::Error>;
+    // Deletes every snapshot of the aggregate type
+    async fn delete_snapshots(&self) -> Result<u64, anyhow::Error>;
+    // Ids of every aggregate with at least one stored event
+    async fn retrieve_aggregate_ids(&self) -> Result<Vec<String>, anyhow::Error>;
     // Outbox
-    // Used by outbox pattern to retrieve events for sending
-    async fn retrieve_outbox_events(&self) -> Result<Vec<OE>, anyhow::Error>;
-    // Used by outbox pattern to remove events after sending
+    // Used by outbox pattern to retrieve at most `limit` events for sending,
+    // oldest first
+    async fn retrieve_outbox_events(&self, limit: i64) -> Result<Vec<OE>, anyhow::Error>;
+    // Deletes the outbox entries of the given event ids, or every entry of the
+    // aggregate type, without sending them
+    async fn delete_outbox_events(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error>;
+    // Leases at most `limit` events for sending to `owner` until `lease` has
+    // passed, oldest first. Aggregates with an event leased by another owner,
+    // or waiting for a retry, are skipped entirely, so the events of an
+    // aggregate are sent in order
+    async fn claim_outbox_events(
+        &self,
+        owner: &str,
+        limit: i64,
+        lease: Duration,
+    ) -> Result<Vec<OE>, anyhow::Error>;
+    // Gives up the leases of `owner` on the events it has not sent
+    async fn release_outbox_events(&self, owner: &str) -> Result<u64, anyhow::Error>;
+    // Counts a failed send of an event leased by `owner` and keeps `error`.
+    // Returns the attempts made so far
+    async fn record_outbox_failure(
+        &self,
+        owner: &str,
+        event_id: &str,
+        error: &str,
+    ) -> Result<i64, anyhow::Error>;
+    // Holds back an event leased by `owner`, and the rest of its aggregate,
+    // until `retry_at`
+    async fn defer_outbox_event(
+        &self,
+        owner: &str,
+        event_id: &str,
+        retry_at: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error>;
+    // Moves an event leased by `owner` from the outbox to the dead letters
+    async fn dead_letter_outbox_event(
+        &self,
+        owner: &str,
+        event_id: &str,
+    ) -> Result<(), anyhow::Error>;
+    // Dead letters of the aggregate type, oldest first
+    async fn retrieve_dead_letters(&self, limit: i64)
+        -> Result<Vec<DeadLetter<OE>>, anyhow::Error>;
+    // Moves the given dead letters, or all of the aggregate type, back to the
+    // outbox with a fresh attempt count
+    async fn requeue_dead_letters(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error>;
+    // Used by outbox pattern to send an event claimed by `owner` and remove it.
+    // The event is sent first and then deleted only while `owner` still holds
+    // the lease. Fails only when the bus did not take the event; once it did,
+    // the outcome of the delete is reported instead. No transaction is held
+    // across the send, so when the lease moved in the meantime, or the delete
+    // fails, the event is sent again: delivery is at least once
     async fn send_and_delete_outbox_event(
         &self,
+        owner: &str,
         event: IE,
         bus: &Arc<dyn EventBus<IE, OE> + Send + Sync>,
-    ) -> Result<(), anyhow::Error>;
+    ) -> Result<OutboxDelivery, anyhow::Error>;
 }
+
+/// The repository shape used by the command side of an aggregate: envelopes in
+/// and out, snapshots in and out.
+pub type AggregateRepository<A> = dyn EventRepository<EventEnvelope<A>, EventEnvelope<A>, AggregateSnapshot<A>, AggregateSnapshot<A>>
+    + Sync
+    + Send;
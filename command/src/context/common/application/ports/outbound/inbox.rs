@@ -0,0 +1,25 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+
+#[async_trait]
+pub trait Inbox: InboxExpiry {
+    // The unit of work a consumer's side effects join, e.g. a database
+    // transaction
+    type Transaction: Send;
+    // Starts a transaction that records `message_id` as processed by
+    // `consumer`. Returns None, without a transaction, when the message was
+    // recorded before. Dropping the transaction rolls the record back
+    async fn begin(
+        &self,
+        consumer: &str,
+        message_id: &str,
+    ) -> Result<Option<Self::Transaction>, anyhow::Error>;
+    async fn commit(&self, transaction: Self::Transaction) -> Result<(), anyhow::Error>;
+}
+
+#[async_trait]
+pub trait InboxExpiry {
+    // Forgets the messages recorded before `before`, so a redelivery of one of
+    // them is processed again. Returns how many were forgotten
+    async fn expire_messages(&self, before: DateTime<Utc>) -> Result<u64, anyhow::Error>;
+}
@@ -1,2 +1,6 @@
+pub mod checkpoint_store;
 pub mod event_bus;
 pub mod event_repository;
+pub mod inbox;
+pub mod outbox_signal;
+pub mod read_model;
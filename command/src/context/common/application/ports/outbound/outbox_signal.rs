@@ -0,0 +1,9 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait OutboxSignal {
+    // Tells the relay that new outbox entries were committed
+    fn notify(&self);
+    // Completes once entries were committed since the last wakeup
+    async fn notified(&self);
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait ReadModelRepository<V> {
+    async fn load(&self, id: &str) -> Result<Option<V>, anyhow::Error>;
+    // Inserts or replaces the view stored under `id`
+    async fn save(&self, id: &str, version: i64, view: V) -> Result<(), anyhow::Error>;
+    async fn delete(&self, id: &str) -> Result<(), anyhow::Error>;
+    // Views whose top-level `field` equals `value` for every `(field, value)` of
+    // `filter`, ordered by id. Fields are compared as text: strings as they
+    // are, numbers and booleans as JSON writes them (`5`, `true`)
+    async fn query(
+        &self,
+        filter: Vec<(String, String)>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<V>, anyhow::Error>;
+    // Creates an empty shadow copy of the read model for a rebuild to write to,
+    // discarding what an earlier unfinished rebuild left behind
+    async fn create_shadow(
+        &self,
+    ) -> Result<Arc<dyn ReadModelRepository<V> + Send + Sync>, anyhow::Error>;
+    // Replaces the read model with its shadow copy in a single step, so readers
+    // see either the old or the rebuilt views
+    async fn swap_shadow(&self) -> Result<(), anyhow::Error>;
+}
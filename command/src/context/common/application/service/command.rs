@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::sync::Arc;
+
+use chrono::Utc;
+use ulid::Ulid;
+
+use crate::context::common::application::ports::outbound::event_repository::EventRepository;
+use crate::context::common::application::ports::outbound::outbox_signal::OutboxSignal;
+use crate::context::common::domain::entity::aggregate::Aggregate;
+use crate::context::common::domain::entity::error::{AggregateError, EventStoreError};
+use crate::context::common::domain::entity::event::{
+    AggregateSnapshot, DomainEvent, EventEnvelope,
+};
+
+use super::snapshot::{SnapshotPolicy, SnapshotProgress, SnapshotRetention};
+
+/// Runs commands against any event sourced aggregate.
+///
+/// The executor owns the load -> handle -> apply -> store cycle so that a
+/// bounded context only has to supply its aggregate, its services and a
+/// repository.
+pub struct CommandExecutor<A, R>
+where
+    A: Aggregate,
+    R: EventRepository<
+            EventEnvelope<A>,
+            EventEnvelope<A>,
+            AggregateSnapshot<A>,
+            AggregateSnapshot<A>,
+        > + Sync
+        + Send
+        + ?Sized,
+{
+    repository: Arc<R>,
+    services: A::Services,
+    max_retries: usize,
+    snapshot_policy: SnapshotPolicy,
+    snapshot_retention: Option<SnapshotRetention>,
+    outbox_signal: Option<Arc<dyn OutboxSignal + Send + Sync>>,
+}
+
+/// An aggregate as rebuilt from the store, plus what the executor needs to
+/// know about its stream.
+struct Loaded<A> {
+    aggregate: A,
+    version: i64,
+    progress: SnapshotProgress,
+}
+
+impl<A, R> CommandExecutor<A, R>
+where
+    A: Aggregate + Clone,
+    A::Command: Clone,
+    A::Error: Error + Send + Sync + 'static,
+    R: EventRepository<
+            EventEnvelope<A>,
+            EventEnvelope<A>,
+            AggregateSnapshot<A>,
+            AggregateSnapshot<A>,
+        > + Sync
+        + Send
+        + ?Sized,
+{
+    pub fn new(repository: Arc<R>, services: A::Services) -> Self {
+        Self {
+            repository,
+            services,
+            max_retries: 0,
+            snapshot_policy: SnapshotPolicy::default(),
+            snapshot_retention: None,
+            outbox_signal: None,
+        }
+    }
+
+    /// Reload the aggregate and re-run the command up to `max_retries` times
+    /// when the append loses a race with another writer.
+    pub fn with_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Persist snapshots of the aggregate according to `policy` instead of
+    /// never taking any.
+    pub fn with_snapshot_policy(mut self, policy: SnapshotPolicy) -> Self {
+        self.snapshot_policy = policy;
+        self
+    }
+
+    /// Keep snapshots according to `retention` when `prune_snapshots` runs
+    /// instead of keeping all of them.
+    pub fn with_snapshot_retention(mut self, retention: SnapshotRetention) -> Self {
+        self.snapshot_retention = Some(retention);
+        self
+    }
+
+    /// Wake the outbox relay through `signal` whenever events were committed,
+    /// instead of leaving them for its next sweep.
+    pub fn with_outbox_signal(mut self, signal: Arc<dyn OutboxSignal + Send + Sync>) -> Self {
+        self.outbox_signal = Some(signal);
+        self
+    }
+
+    /// Rehydrates an aggregate from its latest snapshot (if any) and the events
+    /// committed after it, returning it together with its stream version.
+    pub async fn load(&self, aggregate_id: &str) -> Result<(A, i64), anyhow::Error> {
+        let loaded = self.rehydrate(aggregate_id).await?;
+        Ok((loaded.aggregate, loaded.version))
+    }
+
+    async fn rehydrate(&self, aggregate_id: &str) -> Result<Loaded<A>, anyhow::Error> {
+        let mut aggregate = A::default();
+        let mut version = 0;
+        let snapshot = self
+            .repository
+            .retrieve_latest_snapshot(aggregate_id.to_string())
+            .await?;
+        let (past_events, since) = match snapshot {
+            Some(x) => {
+                aggregate = x.payload;
+                version = x.version;
+                let events = self
+                    .repository
+                    .retrieve_events(aggregate_id.to_string(), Some(x.version))
+                    .await?;
+                (events, Some(x.timestamp))
+            }
+            None => {
+                let events = self
+                    .repository
+                    .retrieve_events(aggregate_id.to_string(), None)
+                    .await?;
+                (events, None)
+            }
+        };
+        let since = since
+            .or_else(|| past_events.first().map(|x| x.timestamp))
+            .unwrap_or_else(Utc::now);
+        let mut progress = SnapshotProgress::new(since);
+        for event in past_events {
+            version = event.version;
+            progress.record(event.payload.size_hint());
+            aggregate.apply(event.payload);
+        }
+        Ok(Loaded {
+            aggregate,
+            version,
+            progress,
+        })
+    }
+
+    /// Deletes the snapshots that fall outside the configured retention and
+    /// returns how many were removed.
+    pub async fn prune_snapshots(&self) -> Result<u64, anyhow::Error> {
+        match &self.snapshot_retention {
+            Some(retention) => {
+                let (keep_last, taken_before) = retention.bounds(Utc::now());
+                self.repository
+                    .prune_snapshots(keep_last, taken_before)
+                    .await
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Drops every snapshot of the aggregate type and takes a fresh one of each
+    /// aggregate from its events, e.g. after the aggregate's shape changed.
+    /// Returns the number of snapshots written.
+    pub async fn regenerate_snapshots(&self) -> Result<usize, anyhow::Error> {
+        self.repository.delete_snapshots().await?;
+        let mut regenerated = 0;
+        for aggregate_id in self.repository.retrieve_aggregate_ids().await? {
+            let loaded = self.rehydrate(&aggregate_id).await?;
+            if loaded.version > 0 {
+                self.repository
+                    .store_snapshot(snapshot_of(aggregate_id, loaded.aggregate, loaded.version))
+                    .await?;
+                regenerated += 1;
+            }
+        }
+        Ok(regenerated)
+    }
+
+    /// Handles `command` against the aggregate identified by `aggregate_id`, or
+    /// against a fresh aggregate when no id is given, and persists the result.
+    pub async fn execute(
+        &self,
+        aggregate_id: Option<String>,
+        command: A::Command,
+        metadata: HashMap<String, String>,
+    ) -> Result<A, anyhow::Error> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .try_execute(aggregate_id.as_deref(), command.clone(), &metadata)
+                .await;
+            match result {
+                Err(e) if attempt < self.max_retries && is_conflict(&e) => attempt += 1,
+                _ => return result,
+            }
+        }
+    }
+
+    async fn try_execute(
+        &self,
+        aggregate_id: Option<&str>,
+        command: A::Command,
+        metadata: &HashMap<String, String>,
+    ) -> Result<A, anyhow::Error> {
+        let Loaded {
+            mut aggregate,
+            version: expected_version,
+            mut progress,
+            ..
+        } = match aggregate_id {
+            Some(id) => self.rehydrate(id).await?,
+            None => Loaded {
+                aggregate: A::default(),
+                version: 0,
+                progress: SnapshotProgress::new(Utc::now()),
+            },
+        };
+        let events = aggregate.handle(command, &self.services).await?;
+        for event in &events {
+            aggregate.apply(event.clone());
+        }
+        let aggregate_id = aggregate
+            .aggregate_id()
+            .ok_or_else(|| AggregateError::MissingAggregateId(A::aggregate_type()))?;
+        let wrapped_events: Vec<EventEnvelope<A>> = events
+            .into_iter()
+            .zip(expected_version + 1..)
+            .map(|(x, version)| EventEnvelope::<A> {
+                aggregate_id: aggregate_id.clone(),
+                aggregate_type: A::aggregate_type(),
+                sequence: x.event_id(),
+                version,
+                position: None,
+                payload: x,
+                metadata: metadata.clone(),
+                timestamp: Utc::now(),
+            })
+            .collect();
+        for event in &wrapped_events {
+            progress.record(event.payload.size_hint());
+        }
+        let version = expected_version + wrapped_events.len() as i64;
+        self.repository
+            .store_events(wrapped_events, expected_version)
+            .await?;
+        if let Some(signal) = &self.outbox_signal {
+            signal.notify();
+        }
+        if self.snapshot_policy.should_snapshot(&progress, Utc::now()) {
+            let snapshot = snapshot_of(aggregate_id, aggregate.clone(), version);
+            if let Err(e) = self.repository.store_snapshot(snapshot).await {
+                println!("Failed to persist snapshot: {:?}", e);
+            }
+        }
+        Ok(aggregate)
+    }
+}
+
+fn snapshot_of<A: Aggregate>(
+    aggregate_id: String,
+    aggregate: A,
+    version: i64,
+) -> AggregateSnapshot<A> {
+    AggregateSnapshot {
+        aggregate_id,
+        aggregate_type: A::aggregate_type(),
+        payload: aggregate,
+        version,
+        snapshot_id: Ulid::new().to_string(),
+        timestamp: Utc::now(),
+    }
+}
+
+fn is_conflict(error: &anyhow::Error) -> bool {
+    matches!(
+        error.downcast_ref::<EventStoreError>(),
+        Some(EventStoreError::ConcurrencyConflict { .. })
+    )
+}
+
+#[cfg(test)]
+mod command_test {
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    use crate::context::common::application::ports::outbound::event_repository::EventRepository;
+    use crate::context::common::application::ports::outbound::outbox_signal::OutboxSignal;
+    use crate::context::common::infrastructure::adapters::secondary::signal::local::LocalOutboxSignal;
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::InMemoryEventRepository;
+    use crate::context::prescription::application::ports::outbound::prescription::MockPrescriptionServices;
+    use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+    use crate::context::prescription::domain::entity::command::{
+        CreatePrescriptionCommand, PrescriptionCommand, UpdatePrescriptionCommand,
+    };
+
+    use super::{CommandExecutor, SnapshotPolicy};
+
+    #[tokio::test]
+    async fn every_n_events_policy_counts_from_the_last_snapshot() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        let executor = CommandExecutor::new(
+            repository.clone(),
+            Box::new(MockPrescriptionServices::new()) as _,
+        )
+        .with_snapshot_policy(SnapshotPolicy::EveryNEvents(2));
+
+        let created = executor
+            .execute(
+                None,
+                PrescriptionCommand::CreatePrescription(CreatePrescriptionCommand {
+                    medication_id: "1234".into(),
+                    patient_id: "5678".into(),
+                    address: "0".into(),
+                }),
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let id = created.id.unwrap();
+        let mut versions = vec![];
+        for i in 1..=4 {
+            executor
+                .execute(
+                    Some(id.clone()),
+                    PrescriptionCommand::UpdatePrescription(UpdatePrescriptionCommand {
+                        id: id.clone(),
+                        address: i.to_string(),
+                    }),
+                    HashMap::new(),
+                )
+                .await
+                .unwrap();
+            let snapshot = repository
+                .retrieve_latest_snapshot(id.clone())
+                .await
+                .unwrap();
+            versions.push(snapshot.map(|x| x.version));
+        }
+
+        assert_eq!(versions, vec![Some(2), Some(2), Some(4), Some(4)]);
+        let (aggregate, version) = executor.load(&id).await.unwrap();
+        assert_eq!(version, 5);
+        assert_eq!(aggregate.address, Some("4".into()));
+    }
+
+    #[tokio::test]
+    async fn committed_commands_wake_the_outbox_relay() {
+        let signal = LocalOutboxSignal::new();
+        let executor = CommandExecutor::new(
+            InMemoryEventRepository::<PrescriptionAggregate>::new(),
+            Box::new(MockPrescriptionServices::new()) as _,
+        )
+        .with_outbox_signal(signal.clone());
+
+        let rejected = executor
+            .execute(
+                Some("missing".into()),
+                PrescriptionCommand::UpdatePrescription(UpdatePrescriptionCommand {
+                    id: "missing".into(),
+                    address: "1".into(),
+                }),
+                HashMap::new(),
+            )
+            .await;
+        assert!(rejected.is_err());
+        let woken = tokio::time::timeout(Duration::from_millis(50), signal.notified()).await;
+        assert!(woken.is_err());
+
+        executor
+            .execute(
+                None,
+                PrescriptionCommand::CreatePrescription(CreatePrescriptionCommand {
+                    medication_id: "1234".into(),
+                    patient_id: "5678".into(),
+                    address: "0".into(),
+                }),
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+        let woken = tokio::time::timeout(Duration::from_millis(50), signal.notified()).await;
+        assert!(woken.is_ok());
+    }
+}
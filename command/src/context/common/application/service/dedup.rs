@@ -0,0 +1,140 @@
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+
+use crate::context::common::domain::entity::aggregate::Aggregate;
+use crate::context::common::domain::entity::event::EventEnvelope;
+
+/// How many message ids a deduplicator remembers by default.
+const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Drops redeliveries on the consumer side of the outbox.
+///
+/// The outbox relay delivers every event at least once: a relay that sent an
+/// event but failed to record that, e.g. because it crashed or lost its
+/// database connection, sends it again. Each delivery carries the same
+/// `EventEnvelope::message_id`, which is what duplicates are recognised by.
+///
+/// Only the ids of the last `capacity` handled messages are kept, in memory,
+/// so a redelivery after a restart or long after the original still gets
+/// through. Consumers that must never repeat a side effect need a durable
+/// record of handled ids instead.
+pub struct MessageDeduplicator {
+    capacity: usize,
+    handled: Mutex<(HashSet<String>, VecDeque<String>)>,
+}
+
+impl Default for MessageDeduplicator {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl MessageDeduplicator {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            handled: Mutex::new((HashSet::new(), VecDeque::new())),
+        }
+    }
+
+    /// Whether the message was handled before.
+    pub fn is_duplicate(&self, message_id: &str) -> bool {
+        match self.handled.lock() {
+            Ok(x) => x.0.contains(message_id),
+            Err(_) => false,
+        }
+    }
+
+    /// Remembers the message as handled, forgetting the oldest one when full.
+    pub fn mark_handled(&self, message_id: &str) -> Result<(), anyhow::Error> {
+        let mut handled = self
+            .handled
+            .lock()
+            .map_err(|_| anyhow!("deduplicator lock poisoned"))?;
+        let (ids, order) = &mut *handled;
+        if ids.insert(message_id.to_string()) {
+            order.push_back(message_id.to_string());
+        }
+        while order.len() > self.capacity {
+            if let Some(x) = order.pop_front() {
+                ids.remove(&x);
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs `handle` unless `event` was handled before, and returns whether it
+    /// ran. Only successful runs are remembered, so a failed event is handled
+    /// again when it is redelivered.
+    pub async fn handle_once<A, F, Fut>(
+        &self,
+        event: &EventEnvelope<A>,
+        handle: F,
+    ) -> Result<bool, anyhow::Error>
+    where
+        A: Aggregate,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<(), anyhow::Error>>,
+    {
+        let message_id = event.message_id();
+        if self.is_duplicate(&message_id) {
+            return Ok(false);
+        }
+        handle().await?;
+        self.mark_handled(&message_id)?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod dedup_test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use anyhow::anyhow;
+
+    use crate::context::prescription::domain::entity::fixture::updated;
+
+    use super::MessageDeduplicator;
+
+    #[tokio::test]
+    async fn redeliveries_are_skipped_unless_the_first_delivery_failed() {
+        let deduplicator = MessageDeduplicator::new(2);
+        let runs = AtomicUsize::new(0);
+        let count = || async {
+            runs.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        };
+
+        let failed = deduplicator
+            .handle_once(&updated("1", 1, "a"), || async { Err(anyhow!("down")) })
+            .await;
+        assert!(failed.is_err());
+        assert!(deduplicator
+            .handle_once(&updated("1", 1, "a"), count)
+            .await
+            .unwrap());
+        assert!(!deduplicator
+            .handle_once(&updated("1", 1, "a"), count)
+            .await
+            .unwrap());
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // The oldest id is forgotten once more than `capacity` were handled.
+        deduplicator
+            .handle_once(&updated("1", 2, "a"), count)
+            .await
+            .unwrap();
+        deduplicator
+            .handle_once(&updated("1", 3, "a"), count)
+            .await
+            .unwrap();
+        assert!(deduplicator
+            .handle_once(&updated("1", 1, "a"), count)
+            .await
+            .unwrap());
+        assert_eq!(runs.load(Ordering::SeqCst), 4);
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+
+use crate::context::common::application::ports::outbound::inbox::Inbox;
+use crate::context::common::domain::entity::aggregate::Aggregate;
+use crate::context::common::domain::entity::event::EventEnvelope;
+
+/// Processes each event at most once per consumer, durably.
+///
+/// Where `MessageDeduplicator` forgets on restart, this records the
+/// `EventEnvelope::message_id` of every processed event in the inbox, in the
+/// same transaction as the consumer's side effects. A redelivery finds the
+/// record and is skipped; a failed run leaves none behind, so the event is
+/// processed again when it is redelivered. Records are kept until the inbox
+/// expires them, which bounds how late a redelivery may arrive.
+pub struct InboxConsumer<I: Inbox> {
+    inbox: Arc<I>,
+    name: String,
+}
+
+impl<I: Inbox + Sync + Send> InboxConsumer<I> {
+    /// `name` tells the consumer's records apart from those of other
+    /// consumers of the same events.
+    pub fn new(inbox: Arc<I>, name: &str) -> Self {
+        Self {
+            inbox,
+            name: name.to_string(),
+        }
+    }
+
+    /// Runs `handle` in an inbox transaction unless `event` was processed
+    /// before, and returns whether it ran. The transaction commits only when
+    /// `handle` succeeds.
+    pub async fn handle_once<A, F>(
+        &self,
+        event: &EventEnvelope<A>,
+        handle: F,
+    ) -> Result<bool, anyhow::Error>
+    where
+        A: Aggregate,
+        F: for<'t> FnOnce(&'t mut I::Transaction) -> BoxFuture<'t, Result<(), anyhow::Error>>,
+    {
+        let mut transaction = match self.inbox.begin(&self.name, &event.message_id()).await? {
+            Some(x) => x,
+            None => return Ok(false),
+        };
+        handle(&mut transaction).await?;
+        self.inbox.commit(transaction).await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod inbox_test {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use chrono::{Duration, Utc};
+    use futures::future::BoxFuture;
+
+    use crate::context::common::application::ports::outbound::inbox::InboxExpiry;
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::{
+        InMemoryInbox, InMemoryInboxTransaction,
+    };
+    use crate::context::prescription::domain::entity::fixture::updated;
+
+    use super::InboxConsumer;
+
+    fn counting(
+        runs: Arc<AtomicUsize>,
+    ) -> impl for<'t> FnOnce(&'t mut InMemoryInboxTransaction) -> BoxFuture<'t, anyhow::Result<()>>
+    {
+        move |_| {
+            Box::pin(async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn events_are_processed_once_per_consumer_until_expired() {
+        let inbox = InMemoryInbox::new();
+        let mailer = InboxConsumer::new(inbox.clone(), "mailer");
+        let auditor = InboxConsumer::new(inbox.clone(), "auditor");
+        let runs = Arc::new(AtomicUsize::new(0));
+        let count = || counting(runs.clone());
+
+        let failed = mailer
+            .handle_once(&updated("1", 1, "a"), |_| {
+                Box::pin(async { Err(anyhow!("down")) })
+            })
+            .await;
+        assert!(failed.is_err());
+        assert!(mailer
+            .handle_once(&updated("1", 1, "a"), count())
+            .await
+            .unwrap());
+        assert!(!mailer
+            .handle_once(&updated("1", 1, "a"), count())
+            .await
+            .unwrap());
+        assert!(auditor
+            .handle_once(&updated("1", 1, "a"), count())
+            .await
+            .unwrap());
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        let expired = inbox
+            .expire_messages(Utc::now() + Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(expired, 2);
+        assert!(mailer
+            .handle_once(&updated("1", 1, "a"), count())
+            .await
+            .unwrap());
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,8 @@
+pub mod command;
+pub mod dedup;
+pub mod inbox;
+pub mod projection;
+pub mod rebuild;
+pub mod retry;
+pub mod snapshot;
+pub mod subscription;
@@ -0,0 +1,210 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use futures::{Stream, StreamExt};
+
+use crate::context::common::application::ports::outbound::{
+    checkpoint_store::CheckpointStore, event_repository::AggregateRepository,
+};
+use crate::context::common::domain::entity::{aggregate::Aggregate, event::EventEnvelope};
+
+use super::rebuild::RebuildLock;
+
+/// Keeps a read model up to date from the events of aggregate `A`.
+#[async_trait]
+pub trait Projection<A: Aggregate>: Send + Sync {
+    /// Identifies the projection's checkpoints; must be stable across releases.
+    fn name(&self) -> String;
+
+    async fn handle(&self, event: &EventEnvelope<A>) -> Result<(), anyhow::Error>;
+}
+
+/// Feeds envelopes to a set of projections, each at least once and in stream
+/// order per aggregate.
+///
+/// Every projection keeps a checkpoint per aggregate. Envelopes it has already
+/// handled are skipped, and when an envelope arrives ahead of the checkpoint
+/// the missing events are read from the event store first, if one was given;
+/// without one the gap is accepted. The checkpoint is saved after `handle`
+/// returns, in a write of its own, so an envelope handled just before a crash
+/// is handled again; `handle` has to cope with seeing an event twice.
+pub struct ProjectionRunner<A: Aggregate + 'static> {
+    projections: Vec<Arc<dyn Projection<A>>>,
+    checkpoints: Arc<dyn CheckpointStore + Send + Sync>,
+    repository: Option<Arc<AggregateRepository<A>>>,
+    lock: Option<RebuildLock>,
+}
+
+impl<A: Aggregate + 'static> ProjectionRunner<A> {
+    pub fn new(checkpoints: Arc<dyn CheckpointStore + Send + Sync>) -> Self {
+        Self {
+            projections: vec![],
+            checkpoints,
+            repository: None,
+            lock: None,
+        }
+    }
+
+    pub fn register(mut self, projection: Arc<dyn Projection<A>>) -> Self {
+        self.projections.push(projection);
+        self
+    }
+
+    /// Read events a projection missed from `repository`.
+    pub fn with_repository(mut self, repository: Arc<AggregateRepository<A>>) -> Self {
+        self.repository = Some(repository);
+        self
+    }
+
+    /// Pauses dispatch while a rebuild holding `lock` swaps in a read model.
+    pub fn with_rebuild_lock(mut self, lock: RebuildLock) -> Self {
+        self.lock = Some(lock);
+        self
+    }
+
+    pub async fn dispatch(&self, event: &EventEnvelope<A>) -> Result<(), anyhow::Error> {
+        let _dispatching = match &self.lock {
+            Some(x) => Some(x.read().await),
+            None => None,
+        };
+        for projection in &self.projections {
+            let name = projection.name();
+            let checkpoint = self
+                .checkpoints
+                .load_checkpoint(&name, &event.aggregate_id)
+                .await?;
+            if event.version <= checkpoint {
+                continue;
+            }
+            // The stored stream already contains `event`, so catching up
+            // handles it as well.
+            if event.version > checkpoint + 1 && self.repository.is_some() {
+                self.catch_up(projection.as_ref(), &event.aggregate_id, checkpoint)
+                    .await?;
+                continue;
+            }
+            projection.handle(event).await?;
+            self.checkpoints
+                .save_checkpoint(&name, &event.aggregate_id, event.version)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Brings every projection up to date with the stored events of
+    /// `aggregate_id`.
+    pub async fn replay_aggregate(&self, aggregate_id: &str) -> Result<(), anyhow::Error> {
+        let _dispatching = match &self.lock {
+            Some(x) => Some(x.read().await),
+            None => None,
+        };
+        for projection in &self.projections {
+            let checkpoint = self
+                .checkpoints
+                .load_checkpoint(&projection.name(), aggregate_id)
+                .await?;
+            self.catch_up(projection.as_ref(), aggregate_id, checkpoint)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Dispatches every envelope of `events` until the stream ends. Failures
+    /// are logged and the envelope is left to be caught up later.
+    pub async fn run<S>(&self, mut events: S)
+    where
+        S: Stream<Item = EventEnvelope<A>> + Unpin,
+    {
+        while let Some(event) = events.next().await {
+            if let Err(e) = self.dispatch(&event).await {
+                println!("Failed to project event {}: {:?}", event.sequence, e);
+            }
+        }
+    }
+
+    async fn catch_up(
+        &self,
+        projection: &dyn Projection<A>,
+        aggregate_id: &str,
+        checkpoint: i64,
+    ) -> Result<(), anyhow::Error> {
+        let repository = match &self.repository {
+            Some(x) => x,
+            None => return Ok(()),
+        };
+        let missed = repository
+            .retrieve_events(aggregate_id.to_string(), Some(checkpoint))
+            .await?;
+        for event in missed {
+            projection.handle(&event).await?;
+            self.checkpoints
+                .save_checkpoint(&projection.name(), aggregate_id, event.version)
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod projection_test {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+
+    use crate::context::common::application::ports::outbound::event_repository::EventRepository;
+    use crate::context::common::domain::entity::event::EventEnvelope;
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::{
+        InMemoryCheckpointStore, InMemoryEventRepository,
+    };
+    use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+    use crate::context::prescription::domain::entity::fixture::updated;
+
+    use super::{Projection, ProjectionRunner};
+
+    #[derive(Default)]
+    struct RecordingProjection {
+        seen: Mutex<Vec<i64>>,
+    }
+
+    #[async_trait]
+    impl Projection<PrescriptionAggregate> for RecordingProjection {
+        fn name(&self) -> String {
+            "recording".into()
+        }
+
+        async fn handle(
+            &self,
+            event: &EventEnvelope<PrescriptionAggregate>,
+        ) -> Result<(), anyhow::Error> {
+            self.seen.lock().unwrap().push(event.version);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn dispatch_skips_handled_events_and_catches_up_on_gaps() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        repository
+            .store_events(
+                vec![
+                    updated("1234", 1, "1234"),
+                    updated("1234", 2, "1234"),
+                    updated("1234", 3, "1234"),
+                ],
+                0,
+            )
+            .await
+            .unwrap();
+        let projection = Arc::new(RecordingProjection::default());
+        let runner = ProjectionRunner::new(InMemoryCheckpointStore::new())
+            .register(projection.clone())
+            .with_repository(repository);
+
+        runner.dispatch(&updated("1234", 1, "1234")).await.unwrap();
+        runner.dispatch(&updated("1234", 1, "1234")).await.unwrap();
+        runner.dispatch(&updated("1234", 3, "1234")).await.unwrap();
+        runner.dispatch(&updated("1234", 2, "1234")).await.unwrap();
+
+        assert_eq!(*projection.seen.lock().unwrap(), vec![1, 2, 3]);
+    }
+}
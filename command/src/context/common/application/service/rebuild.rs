@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tokio::sync::RwLock;
+
+use crate::context::common::application::ports::outbound::{
+    checkpoint_store::CheckpointStore, event_repository::AggregateRepository,
+    read_model::ReadModelRepository,
+};
+use crate::context::common::domain::entity::aggregate::Aggregate;
+
+use super::projection::Projection;
+
+/// How many events a rebuild reads per batch by default.
+const DEFAULT_BATCH_SIZE: i64 = 500;
+
+/// Held shared by a `ProjectionRunner` while it dispatches an event and
+/// exclusively by a `ProjectionRebuild` while it swaps in the rebuilt read
+/// model, so the live runner never writes to a table that is being replaced.
+/// Only coordinates the runner and rebuilds of one process: a rebuild run by
+/// another process, such as `command replay`, is not seen by the runner of a
+/// running `serve`, whose writes may then land in the replaced table or move
+/// checkpoints under the rebuild. Rebuilds beside a running `serve` go through
+/// its admin endpoint instead.
+pub type RebuildLock = Arc<RwLock<()>>;
+
+/// Builds the projection that maintains the given read model.
+pub type ProjectionFactory<A, V> = Box<
+    dyn Fn(Arc<dyn ReadModelRepository<V> + Send + Sync>) -> Arc<dyn Projection<A>> + Send + Sync,
+>;
+
+/// How far a rebuild got.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RebuildProgress {
+    /// Events replayed so far.
+    pub events: usize,
+    /// Global position of the last replayed event.
+    pub position: i64,
+}
+
+/// Wipes a read model and rebuilds it from every stored event of aggregate
+/// `A`.
+///
+/// The projection writes to a shadow copy of the read model while readers
+/// keep using the live one, which is only replaced once the replay is done.
+/// Whatever the live runner wrote to the old table in the meantime is lost
+/// with it, so with live dispatch paused by the rebuild lock, the events
+/// appended since the replay are applied to the swapped table as well. The
+/// projection's checkpoints then move to the versions applied, but never
+/// backwards past what the live runner already saved.
+pub struct ProjectionRebuild<A: Aggregate + 'static, V> {
+    repository: Arc<AggregateRepository<A>>,
+    checkpoints: Arc<dyn CheckpointStore + Send + Sync>,
+    views: Arc<dyn ReadModelRepository<V> + Send + Sync>,
+    projection: ProjectionFactory<A, V>,
+    batch_size: i64,
+    lock: RebuildLock,
+}
+
+impl<A: Aggregate + 'static, V> ProjectionRebuild<A, V> {
+    pub fn new(
+        repository: Arc<AggregateRepository<A>>,
+        checkpoints: Arc<dyn CheckpointStore + Send + Sync>,
+        views: Arc<dyn ReadModelRepository<V> + Send + Sync>,
+        projection: ProjectionFactory<A, V>,
+    ) -> Self {
+        Self {
+            repository,
+            checkpoints,
+            views,
+            projection,
+            batch_size: DEFAULT_BATCH_SIZE,
+            lock: RebuildLock::default(),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Shares `lock` with the live `ProjectionRunner` of the read model.
+    pub fn with_rebuild_lock(mut self, lock: RebuildLock) -> Self {
+        self.lock = lock;
+        self
+    }
+
+    /// Runs the rebuild, calling `report` after every batch.
+    pub async fn run<F>(&self, report: F) -> Result<RebuildProgress, anyhow::Error>
+    where
+        F: Fn(&RebuildProgress) + Send + Sync,
+    {
+        let shadow = self.views.create_shadow().await?;
+        let projection = (self.projection)(shadow);
+        let mut progress = RebuildProgress::default();
+        let mut versions: HashMap<String, i64> = HashMap::new();
+        self.replay(projection.as_ref(), &mut progress, &mut versions, &report)
+            .await?;
+
+        let _paused = self.lock.write().await;
+        self.views.swap_shadow().await?;
+        let live = (self.projection)(self.views.clone());
+        self.replay(live.as_ref(), &mut progress, &mut versions, &report)
+            .await?;
+        let name = projection.name();
+        for (aggregate_id, version) in versions {
+            let saved = self
+                .checkpoints
+                .load_checkpoint(&name, &aggregate_id)
+                .await?;
+            if version > saved {
+                self.checkpoints
+                    .save_checkpoint(&name, &aggregate_id, version)
+                    .await?;
+            }
+        }
+        Ok(progress)
+    }
+
+    /// Feeds `projection` the events after `progress` until there are none
+    /// left, recording the last version applied per aggregate.
+    async fn replay<F>(
+        &self,
+        projection: &dyn Projection<A>,
+        progress: &mut RebuildProgress,
+        versions: &mut HashMap<String, i64>,
+        report: &F,
+    ) -> Result<(), anyhow::Error>
+    where
+        F: Fn(&RebuildProgress) + Send + Sync,
+    {
+        loop {
+            let events = self
+                .repository
+                .retrieve_all_events(progress.position, self.batch_size)
+                .await?;
+            if events.is_empty() {
+                return Ok(());
+            }
+            for event in &events {
+                projection.handle(event).await?;
+                versions.insert(event.aggregate_id.clone(), event.version);
+                progress.events += 1;
+                progress.position = event.position.unwrap_or(progress.position);
+            }
+            report(progress);
+        }
+    }
+}
+
+#[cfg(test)]
+mod rebuild_test {
+    use std::sync::Arc;
+
+    use crate::context::common::application::ports::outbound::{
+        checkpoint_store::CheckpointStore, event_repository::EventRepository,
+        read_model::ReadModelRepository,
+    };
+    use crate::context::common::application::service::projection::Projection;
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::{
+        InMemoryCheckpointStore, InMemoryEventRepository, InMemoryReadModelStore,
+    };
+    use crate::context::prescription::application::service::projection::{
+        PrescriptionViewProjection, PRESCRIPTION_VIEW_PROJECTION,
+    };
+    use crate::context::prescription::domain::entity::{
+        aggregate::PrescriptionAggregate,
+        fixture::{created, updated},
+        view::PrescriptionView,
+    };
+
+    use super::{ProjectionRebuild, RebuildLock};
+
+    #[tokio::test]
+    async fn rebuild_replaces_stale_views_and_moves_checkpoints() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        repository
+            .store_events(vec![created("1", "old"), updated("1", 2, "new")], 0)
+            .await
+            .unwrap();
+        let checkpoints = InMemoryCheckpointStore::new();
+        let views = InMemoryReadModelStore::<PrescriptionView>::new();
+        let stale = PrescriptionView {
+            id: "2".into(),
+            patient_id: "q".into(),
+            medication_id: "m".into(),
+            address: "gone".into(),
+        };
+        views.save("2", 1, stale).await.unwrap();
+        let rebuild = ProjectionRebuild::new(
+            repository,
+            checkpoints.clone(),
+            views.clone(),
+            Box::new(|x| Arc::new(PrescriptionViewProjection::new(x))),
+        )
+        .with_batch_size(1);
+
+        let reports = std::sync::Mutex::new(vec![]);
+        let progress = rebuild
+            .run(|x| reports.lock().unwrap().push(x.events))
+            .await
+            .unwrap();
+
+        assert_eq!(progress.events, 2);
+        assert_eq!(*reports.lock().unwrap(), vec![1, 2]);
+        assert!(views.load("2").await.unwrap().is_none());
+        assert_eq!(views.load("1").await.unwrap().unwrap().address, "new");
+        let checkpoint = checkpoints
+            .load_checkpoint(PRESCRIPTION_VIEW_PROJECTION, "1")
+            .await
+            .unwrap();
+        assert_eq!(checkpoint, 2);
+    }
+
+    #[tokio::test]
+    async fn events_appended_before_the_swap_reach_the_rebuilt_views() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        repository
+            .store_events(vec![created("1", "old")], 0)
+            .await
+            .unwrap();
+        let checkpoints = InMemoryCheckpointStore::new();
+        let views = InMemoryReadModelStore::<PrescriptionView>::new();
+        let lock = RebuildLock::default();
+        let rebuild = Arc::new(
+            ProjectionRebuild::new(
+                repository.clone(),
+                checkpoints.clone(),
+                views.clone(),
+                Box::new(|x| Arc::new(PrescriptionViewProjection::new(x))),
+            )
+            .with_rebuild_lock(lock.clone()),
+        );
+
+        // The live runner is dispatching when the replay is done, so the
+        // rebuild waits for it before swapping.
+        let dispatching = lock.read().await;
+        let running = tokio::spawn({
+            let rebuild = rebuild.clone();
+            async move { rebuild.run(|_| {}).await }
+        });
+        while lock.try_read().is_ok() {
+            tokio::task::yield_now().await;
+        }
+        let event = updated("1", 2, "new");
+        repository
+            .store_events(vec![event.clone()], 1)
+            .await
+            .unwrap();
+        PrescriptionViewProjection::new(views.clone())
+            .handle(&event)
+            .await
+            .unwrap();
+        checkpoints
+            .save_checkpoint(PRESCRIPTION_VIEW_PROJECTION, "1", 2)
+            .await
+            .unwrap();
+        drop(dispatching);
+
+        let progress = running.await.unwrap().unwrap();
+        assert_eq!(progress.events, 2);
+        assert_eq!(views.load("1").await.unwrap().unwrap().address, "new");
+        let checkpoint = checkpoints
+            .load_checkpoint(PRESCRIPTION_VIEW_PROJECTION, "1")
+            .await
+            .unwrap();
+        assert_eq!(checkpoint, 2);
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Decides when the outbox relay tries an event again after the bus failed to
+/// take it, and when it gives up and dead-letters the event.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Sends attempted before an event is dead-lettered.
+    pub max_attempts: i64,
+    /// Delay after the first failed send; every further failure doubles it.
+    pub base_delay: Duration,
+    /// Upper bound of the delay.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 10,
+            base_delay: Duration::seconds(1),
+            max_delay: Duration::minutes(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// When to send again after `attempts` failed sends, or `None` once the
+    /// event should be dead-lettered.
+    pub fn retry_at(&self, attempts: i64, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if attempts >= self.max_attempts {
+            return None;
+        }
+        let factor = 1_i64 << (attempts - 1).clamp(0, 30);
+        let delay = self.base_delay.num_milliseconds().saturating_mul(factor);
+        Some(now + Duration::milliseconds(delay).min(self.max_delay))
+    }
+}
+
+#[cfg(test)]
+mod retry_test {
+    use chrono::{Duration, Utc};
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn delays_double_up_to_the_cap_until_attempts_run_out() {
+        let now = Utc::now();
+        let policy = RetryPolicy {
+            max_attempts: 40,
+            base_delay: Duration::seconds(2),
+            max_delay: Duration::seconds(60),
+        };
+
+        let delays: Vec<Duration> = [1, 2, 3, 5, 6, 39]
+            .iter()
+            .map(|x| policy.retry_at(*x, now).unwrap() - now)
+            .collect();
+
+        assert_eq!(
+            delays,
+            [2, 4, 8, 32, 60, 60].map(Duration::seconds).to_vec()
+        );
+        assert_eq!(policy.retry_at(40, now), None);
+    }
+}
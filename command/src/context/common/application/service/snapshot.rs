@@ -0,0 +1,113 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// Decides when the `CommandExecutor` persists a snapshot of an aggregate.
+///
+/// Each executor, and therefore each aggregate type, carries its own policy.
+#[derive(Clone, Debug, Default)]
+pub enum SnapshotPolicy {
+    /// Never snapshot; aggregates are always rebuilt from their full stream.
+    #[default]
+    Never,
+    /// Snapshot once this many events were committed since the last snapshot.
+    EveryNEvents(i64),
+    /// Snapshot once the last snapshot, or the start of the stream when there
+    /// is none, is older than the given interval.
+    Interval(Duration),
+    /// Snapshot once the events committed since the last snapshot add up to
+    /// this many bytes, as reported by `DomainEvent::size_hint`.
+    EventBytes(usize),
+}
+
+/// Which snapshots survive pruning. The newest snapshot of an aggregate is
+/// always kept, as it is the one loads start from.
+#[derive(Clone, Debug)]
+pub enum SnapshotRetention {
+    /// Keep the newest K snapshots of every aggregate.
+    KeepLast(i64),
+    /// Keep snapshots younger than the given age.
+    MaxAge(Duration),
+}
+
+impl SnapshotRetention {
+    /// The `(keep_last, taken_before)` bounds handed to
+    /// `EventRepository::prune_snapshots`.
+    pub fn bounds(&self, now: DateTime<Utc>) -> (i64, Option<DateTime<Utc>>) {
+        match self {
+            SnapshotRetention::KeepLast(k) => ((*k).max(1), None),
+            SnapshotRetention::MaxAge(age) => (1, Some(now - *age)),
+        }
+    }
+}
+
+/// What has happened to a stream since its last snapshot.
+#[derive(Clone, Debug)]
+pub struct SnapshotProgress {
+    /// Events committed after the last snapshot.
+    pub events: i64,
+    /// Summed `size_hint` of those events.
+    pub bytes: usize,
+    /// When the last snapshot was taken, or when the stream started.
+    pub since: DateTime<Utc>,
+}
+
+impl SnapshotProgress {
+    pub fn new(since: DateTime<Utc>) -> Self {
+        Self {
+            events: 0,
+            bytes: 0,
+            since,
+        }
+    }
+
+    pub fn record(&mut self, size_hint: usize) {
+        self.events += 1;
+        self.bytes += size_hint;
+    }
+}
+
+impl SnapshotPolicy {
+    pub fn should_snapshot(&self, progress: &SnapshotProgress, now: DateTime<Utc>) -> bool {
+        if progress.events == 0 {
+            return false;
+        }
+        match self {
+            SnapshotPolicy::Never => false,
+            SnapshotPolicy::EveryNEvents(n) => progress.events >= *n,
+            SnapshotPolicy::Interval(interval) => now - progress.since >= *interval,
+            SnapshotPolicy::EventBytes(bytes) => progress.bytes >= *bytes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod snapshot_test {
+    use chrono::{Duration, Utc};
+
+    use super::{SnapshotPolicy, SnapshotProgress};
+
+    #[test]
+    fn policies_trigger_on_their_own_threshold_only() {
+        let now = Utc::now();
+        let mut progress = SnapshotProgress::new(now - Duration::minutes(5));
+        for _ in 0..3 {
+            progress.record(100);
+        }
+
+        assert!(!SnapshotPolicy::Never.should_snapshot(&progress, now));
+        assert!(SnapshotPolicy::EveryNEvents(3).should_snapshot(&progress, now));
+        assert!(!SnapshotPolicy::EveryNEvents(4).should_snapshot(&progress, now));
+        assert!(SnapshotPolicy::Interval(Duration::minutes(5)).should_snapshot(&progress, now));
+        assert!(!SnapshotPolicy::Interval(Duration::minutes(6)).should_snapshot(&progress, now));
+        assert!(SnapshotPolicy::EventBytes(300).should_snapshot(&progress, now));
+        assert!(!SnapshotPolicy::EventBytes(301).should_snapshot(&progress, now));
+    }
+
+    #[test]
+    fn policies_never_trigger_without_new_events() {
+        let now = Utc::now();
+        let progress = SnapshotProgress::new(now - Duration::days(1));
+
+        assert!(!SnapshotPolicy::Interval(Duration::minutes(1)).should_snapshot(&progress, now));
+        assert!(!SnapshotPolicy::EveryNEvents(0).should_snapshot(&progress, now));
+    }
+}
@@ -0,0 +1,263 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::anyhow;
+
+use crate::context::common::application::ports::outbound::{
+    checkpoint_store::CheckpointStore, event_repository::AggregateRepository,
+    outbox_signal::OutboxSignal,
+};
+use crate::context::common::domain::entity::aggregate::Aggregate;
+
+use super::projection::Projection;
+
+/// How many events a subscription reads per batch by default.
+const DEFAULT_BATCH_SIZE: i64 = 100;
+/// How long a caught up subscription waits before looking for new events when
+/// no commit signal wakes it earlier.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Feeds the whole event history of aggregate `A` to a handler in global
+/// position order, then keeps following newly appended events.
+///
+/// Once caught up it waits for the signal given with `with_signal`, which the
+/// event store notifies on every commit, and looks anyway after
+/// `poll_interval`, as a fallback for commits the signal missed. Notifications
+/// wake a single waiter, so the subscription needs a signal of its own rather
+/// than the outbox relay's.
+///
+/// The position reached is stored under the handler's name after every batch,
+/// so a restarted subscription continues where it stopped. Delivery is at
+/// least once: events of a batch that failed part way may be handled again.
+pub struct CatchUpSubscription<A: Aggregate + 'static> {
+    handler: Arc<dyn Projection<A>>,
+    repository: Arc<AggregateRepository<A>>,
+    checkpoints: Arc<dyn CheckpointStore + Send + Sync>,
+    batch_size: i64,
+    poll_interval: Duration,
+    signal: Option<Arc<dyn OutboxSignal + Send + Sync>>,
+}
+
+impl<A: Aggregate + 'static> CatchUpSubscription<A> {
+    pub fn new(
+        handler: Arc<dyn Projection<A>>,
+        repository: Arc<AggregateRepository<A>>,
+        checkpoints: Arc<dyn CheckpointStore + Send + Sync>,
+    ) -> Self {
+        Self {
+            handler,
+            repository,
+            checkpoints,
+            batch_size: DEFAULT_BATCH_SIZE,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            signal: None,
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    pub fn with_signal(mut self, signal: Arc<dyn OutboxSignal + Send + Sync>) -> Self {
+        self.signal = Some(signal);
+        self
+    }
+
+    /// Rewinds the subscription so the next run replays from the first event.
+    pub async fn reset(&self) -> Result<(), anyhow::Error> {
+        self.checkpoints
+            .save_position(&self.handler.name(), 0)
+            .await
+    }
+
+    /// Handles the next batch after the stored position and returns how many
+    /// events it contained; 0 means the subscription is caught up. Fails on an
+    /// event without a position, as the subscription could not move past it.
+    pub async fn poll(&self) -> Result<usize, anyhow::Error> {
+        let name = self.handler.name();
+        let position = self.checkpoints.load_position(&name).await?;
+        let events = self
+            .repository
+            .retrieve_all_events(position, self.batch_size)
+            .await?;
+        let mut reached = position;
+        let mut result = Ok(events.len());
+        for event in &events {
+            let position = match event.position {
+                Some(x) => x,
+                None => {
+                    result = Err(anyhow!(
+                        "event {} of aggregate `{}` has no position",
+                        event.version,
+                        event.aggregate_id
+                    ));
+                    break;
+                }
+            };
+            if let Err(e) = self.handler.handle(event).await {
+                result = Err(e);
+                break;
+            }
+            reached = position;
+        }
+        if reached > position {
+            self.checkpoints.save_position(&name, reached).await?;
+        }
+        result
+    }
+
+    /// Processes batches until no events are left and returns how many were
+    /// handled.
+    pub async fn catch_up(&self) -> Result<usize, anyhow::Error> {
+        let mut handled = 0;
+        loop {
+            match self.poll().await? {
+                0 => return Ok(handled),
+                x => handled += x,
+            }
+        }
+    }
+
+    /// Catches up and then tails the store forever, waiting for the next
+    /// commit, or at most `poll_interval`, whenever there is nothing new.
+    /// Failures are logged and retried after `poll_interval`.
+    pub async fn run(&self) {
+        loop {
+            match self.poll().await {
+                Ok(0) => match &self.signal {
+                    Some(signal) => {
+                        tokio::select! {
+                            _ = signal.notified() => {}
+                            _ = tokio::time::sleep(self.poll_interval) => {}
+                        }
+                    }
+                    None => tokio::time::sleep(self.poll_interval).await,
+                },
+                Ok(_) => {}
+                Err(e) => {
+                    println!(
+                        "Subscription {} failed, retrying: {:?}",
+                        self.handler.name(),
+                        e
+                    );
+                    tokio::time::sleep(self.poll_interval).await
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod subscription_test {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use crate::context::common::application::ports::outbound::event_repository::EventRepository;
+    use crate::context::common::application::ports::outbound::outbox_signal::OutboxSignal;
+    use crate::context::common::application::service::projection::Projection;
+    use crate::context::common::domain::entity::event::EventEnvelope;
+    use crate::context::common::infrastructure::adapters::secondary::signal::local::LocalOutboxSignal;
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::{
+        InMemoryCheckpointStore, InMemoryEventRepository,
+    };
+    use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+    use crate::context::prescription::domain::entity::fixture::updated;
+
+    use super::CatchUpSubscription;
+
+    #[derive(Default)]
+    struct RecordingHandler {
+        seen: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Projection<PrescriptionAggregate> for RecordingHandler {
+        fn name(&self) -> String {
+            "recording".into()
+        }
+
+        async fn handle(
+            &self,
+            event: &EventEnvelope<PrescriptionAggregate>,
+        ) -> Result<(), anyhow::Error> {
+            self.seen.lock().unwrap().push(event.sequence.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn subscription_resumes_from_checkpoint_and_replays_after_reset() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        let checkpoints = InMemoryCheckpointStore::new();
+        let handler = Arc::new(RecordingHandler::default());
+        let subscription =
+            CatchUpSubscription::new(handler.clone(), repository.clone(), checkpoints.clone())
+                .with_batch_size(2);
+        repository
+            .store_events(vec![updated("a", 1, "1234"), updated("a", 2, "1234")], 0)
+            .await
+            .unwrap();
+        repository
+            .store_events(vec![updated("b", 1, "1234")], 0)
+            .await
+            .unwrap();
+
+        assert_eq!(subscription.catch_up().await.unwrap(), 3);
+        repository
+            .store_events(vec![updated("a", 3, "1234")], 2)
+            .await
+            .unwrap();
+        assert_eq!(subscription.catch_up().await.unwrap(), 1);
+        subscription.reset().await.unwrap();
+        assert_eq!(subscription.catch_up().await.unwrap(), 4);
+
+        assert_eq!(
+            *handler.seen.lock().unwrap(),
+            vec!["a-1", "a-2", "b-1", "a-3", "a-1", "a-2", "b-1", "a-3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn caught_up_subscription_wakes_on_commit_signal() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        let handler = Arc::new(RecordingHandler::default());
+        let signal = LocalOutboxSignal::new();
+        let subscription = Arc::new(
+            CatchUpSubscription::new(
+                handler.clone(),
+                repository.clone(),
+                InMemoryCheckpointStore::new(),
+            )
+            .with_poll_interval(Duration::from_secs(3600))
+            .with_signal(signal.clone()),
+        );
+        let running = tokio::spawn({
+            let subscription = subscription.clone();
+            async move { subscription.run().await }
+        });
+
+        repository
+            .store_events(vec![updated("a", 1, "1234")], 0)
+            .await
+            .unwrap();
+        signal.notify();
+        let seen = tokio::time::timeout(Duration::from_secs(5), async {
+            while handler.seen.lock().unwrap().is_empty() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await;
+        running.abort();
+
+        assert!(seen.is_ok());
+        assert_eq!(*handler.seen.lock().unwrap(), vec!["a-1"]);
+    }
+}
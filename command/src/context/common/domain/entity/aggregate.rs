@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use super::event::{AggregateSnapshot, DomainEvent};
+use super::event::DomainEvent;
 use async_trait::async_trait;
 
 #[async_trait]
@@ -21,6 +21,4 @@ pub trait Aggregate: Default + Sync + Send {
     ) -> Result<Vec<Self::Event>, Self::Error>;
 
     fn apply(&mut self, event: Self::Event);
-
-    fn snapshot(&mut self) -> Option<AggregateSnapshot<Self>>;
 }
@@ -0,0 +1,76 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum AggregateError {
+    #[error("aggregate of type `{0}` has no id after applying its events")]
+    MissingAggregateId(String),
+}
+
+#[derive(Error, Debug)]
+pub enum EventStoreError {
+    #[error("aggregate `{aggregate_id}` was modified concurrently: expected version {expected}, found {actual}")]
+    ConcurrencyConflict {
+        aggregate_id: String,
+        expected: i64,
+        actual: i64,
+    },
+    #[error("failed to append event {version} of aggregate `{aggregate_id}`")]
+    EventAppend {
+        aggregate_id: String,
+        version: i64,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("failed to write outbox entry for event {version} of aggregate `{aggregate_id}`")]
+    OutboxAppend {
+        aggregate_id: String,
+        version: i64,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("failed to decode {event_type} event stored at version {event_version}")]
+    EventDecode {
+        event_type: String,
+        event_version: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("failed to store snapshot of aggregate `{aggregate_id}`")]
+    SnapshotAppend {
+        aggregate_id: String,
+        #[source]
+        source: anyhow::Error,
+    },
+    #[error("failed to commit events of aggregate `{aggregate_id}`")]
+    Commit {
+        aggregate_id: String,
+        #[source]
+        source: anyhow::Error,
+    },
+}
+
+#[derive(Error, Debug)]
+pub enum ReadModelError {
+    #[error("`{0}` is not a valid read model field name")]
+    InvalidField(String),
+    #[error("no projection named `{0}`")]
+    UnknownProjection(String),
+    #[error("projection `{0}` is already being rebuilt")]
+    RebuildInProgress(String),
+}
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("failed to read configuration file `{path}`")]
+    Read {
+        path: String,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("failed to parse {origin}: {message}")]
+    Parse { origin: String, message: String },
+    #[error("`{0}` is not a setting of the form SECTION.KEY=VALUE")]
+    Malformed(String),
+    #[error("invalid configuration: {}", .0.join("; "))]
+    Invalid(Vec<String>),
+}
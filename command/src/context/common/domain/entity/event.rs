@@ -2,6 +2,11 @@ use chrono::{DateTime, Utc};
 use std::{collections::HashMap, fmt::Debug};
 
 use super::aggregate::Aggregate;
+use super::filter::topic;
+
+/// Metadata key under which the outbox relay stamps `EventEnvelope::message_id`
+/// on every message it sends.
+pub const MESSAGE_ID: &str = "message_id";
 
 pub trait DomainEvent: Clone + PartialEq + Debug + Sync + Send {
     fn event_type(&self) -> String;
@@ -9,6 +14,10 @@ pub trait DomainEvent: Clone + PartialEq + Debug + Sync + Send {
     fn event_version(&self) -> String;
 
     fn event_id(&self) -> String;
+
+    /// Approximate size of the event in bytes, used by size-based snapshot
+    /// policies.
+    fn size_hint(&self) -> usize;
 }
 
 #[derive(Debug)]
@@ -20,8 +29,13 @@ where
     pub aggregate_id: String,
     /// The type of aggregate instance
     pub aggregate_type: String,
-    /// The sequence id for an aggregate instance.
+    /// The unique id (a ULID) of the event. Streams are ordered by `version`.
     pub sequence: String,
+    /// The position of this event in its aggregate stream, starting at 1.
+    pub version: i64,
+    /// The gap-free position of this event across all streams of the store,
+    /// assigned on append; `None` until the event is stored.
+    pub position: Option<i64>,
     /// The event payload with all business information.
     pub payload: A::Event,
     /// Additional metadata for use in auditing, logging or debugging purposes.
@@ -30,15 +44,34 @@ where
     pub timestamp: DateTime<Utc>,
 }
 
+impl<A: Aggregate> EventEnvelope<A> {
+    /// Identifies this event on the bus: the same for every delivery of the
+    /// event, as it is derived from the event's place in its stream rather
+    /// than from the send.
+    pub fn message_id(&self) -> String {
+        format!(
+            "{}:{}:{}",
+            self.aggregate_type, self.aggregate_id, self.version
+        )
+    }
+
+    /// The topic the event is routed under, see `filter::topic`.
+    pub fn topic(&self) -> String {
+        topic(&self.aggregate_type, &self.payload.event_type())
+    }
+}
+
 impl<A: Aggregate> Clone for EventEnvelope<A> {
     fn clone(&self) -> Self {
         EventEnvelope {
             aggregate_id: self.aggregate_id.clone(),
             aggregate_type: self.aggregate_type.clone(),
             sequence: self.sequence.clone(),
+            version: self.version,
+            position: self.position,
             payload: self.payload.clone(),
             metadata: self.metadata.clone(),
-            timestamp: self.timestamp.clone(),
+            timestamp: self.timestamp,
         }
     }
 }
@@ -51,10 +84,37 @@ pub struct AggregateSnapshot<S> {
     pub aggregate_type: String,
     /// The current state of the aggregate instance (e.g. the snapshot data)
     pub payload: S,
-    /// The last committed event sequence ULID for this aggregate instance.
-    pub last_sequence: String,
+    /// The stream version of the last event folded into this snapshot; loads
+    /// continue with the events after it.
+    pub version: i64,
     /// The id of this snapshot
     pub snapshot_id: String,
     /// Timestamp of when this event was produced
     pub timestamp: DateTime<Utc>,
 }
+
+/// What became of an outbox entry once the bus took its event.
+#[derive(Debug)]
+pub enum OutboxDelivery {
+    /// The entry was removed from the outbox.
+    Sent,
+    /// The lease had moved to another owner, so the entry stays in the outbox
+    /// and that owner sends the event again.
+    LeaseLost,
+    /// Removing the entry failed, so the event is sent again once the entry is
+    /// claimed next.
+    NotDeleted(anyhow::Error),
+}
+
+/// An outbox entry that was given up on after repeated failed sends.
+#[derive(Clone, Debug)]
+pub struct DeadLetter<E> {
+    /// The event that could not be sent
+    pub event: E,
+    /// How many sends were attempted
+    pub attempts: i64,
+    /// The error of the last attempt
+    pub last_error: String,
+    /// When the entry left the outbox
+    pub dead_lettered_at: DateTime<Utc>,
+}
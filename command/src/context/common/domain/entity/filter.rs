@@ -0,0 +1,125 @@
+use super::{
+    aggregate::Aggregate,
+    event::{DomainEvent, EventEnvelope},
+};
+
+/// Stands for any aggregate or event type in a topic pattern.
+const WILDCARD: &str = "*";
+
+/// The topic an event is routed under: its aggregate type, as given by
+/// `Aggregate::aggregate_type`, and its `DomainEvent::event_type`, e.g.
+/// `Prescription.PrescriptionUpdated`.
+pub fn topic(aggregate_type: &str, event_type: &str) -> String {
+    format!("{}.{}", aggregate_type, event_type)
+}
+
+/// Which events a subscription receives.
+///
+/// An event passes when its topic matches one of the filter's topic patterns,
+/// or the filter has none, and its metadata holds every required entry. A
+/// pattern is a topic whose aggregate or event type may be `*`, e.g.
+/// `Prescription.*`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventFilter {
+    topics: Vec<(String, String)>,
+    metadata: Vec<(String, String)>,
+}
+
+impl EventFilter {
+    /// A filter letting every event through.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Lets the events of aggregate type `aggregate_type` through.
+    pub fn with_aggregate_type(self, aggregate_type: &str) -> Self {
+        self.with_topic_parts(aggregate_type, WILDCARD)
+    }
+
+    /// Lets the events of type `event_type` through, whatever their aggregate.
+    pub fn with_event_type(self, event_type: &str) -> Self {
+        self.with_topic_parts(WILDCARD, event_type)
+    }
+
+    /// Lets the events whose topic matches `pattern` through.
+    pub fn with_topic(self, pattern: &str) -> Self {
+        match pattern.split_once('.') {
+            Some((x, y)) => self.with_topic_parts(x, y),
+            None => self.with_aggregate_type(pattern),
+        }
+    }
+
+    /// Only lets events through whose metadata maps `key` to `value`.
+    pub fn with_metadata(mut self, key: &str, value: &str) -> Self {
+        self.metadata.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    fn with_topic_parts(mut self, aggregate_type: &str, event_type: &str) -> Self {
+        self.topics
+            .push((aggregate_type.to_string(), event_type.to_string()));
+        self
+    }
+
+    pub fn matches<A: Aggregate>(&self, event: &EventEnvelope<A>) -> bool {
+        let part = |pattern: &str, value: &str| pattern == WILDCARD || pattern == value;
+        let metadata = self
+            .metadata
+            .iter()
+            .all(|(key, value)| event.metadata.get(key) == Some(value));
+        if !metadata {
+            return false;
+        }
+        if self.topics.is_empty() {
+            return true;
+        }
+        // The event type is only worked out once the aggregate type matched.
+        let mut event_type = None;
+        self.topics.iter().any(|(aggregate_type, x)| {
+            part(aggregate_type, &event.aggregate_type)
+                && (x == WILDCARD
+                    || x == event_type.get_or_insert_with(|| event.payload.event_type()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod filter_test {
+    use crate::context::common::domain::entity::event::EventEnvelope;
+    use crate::context::prescription::domain::entity::{
+        aggregate::PrescriptionAggregate, fixture::updated,
+    };
+
+    use super::EventFilter;
+
+    fn for_tenant(tenant: &str) -> EventEnvelope<PrescriptionAggregate> {
+        let mut event = updated("1", 2, "a");
+        event.metadata.insert("tenant".into(), tenant.into());
+        event
+    }
+
+    #[test]
+    fn events_pass_on_any_topic_and_all_metadata() {
+        let event = for_tenant("acme");
+        assert_eq!(event.topic(), "Prescription.PrescriptionUpdated");
+
+        assert!(EventFilter::all().matches(&event));
+        assert!(EventFilter::all()
+            .with_aggregate_type("Prescription")
+            .matches(&event));
+        assert!(EventFilter::all()
+            .with_event_type("PrescriptionCreated")
+            .with_topic("*.PrescriptionUpdated")
+            .matches(&event));
+        assert!(!EventFilter::all()
+            .with_topic("Prescription.PrescriptionCreated")
+            .matches(&event));
+        assert!(!EventFilter::all().with_topic("Order").matches(&event));
+
+        let tenant = EventFilter::all()
+            .with_topic("Prescription.PrescriptionUpdated")
+            .with_metadata("tenant", "acme");
+        assert!(tenant.matches(&event));
+        assert!(!tenant.matches(&for_tenant("other")));
+    }
+}
@@ -49,12 +49,9 @@ where
     where
         K: Clone,
     {
-        for transition in self.transitions.iter() {
-            if (transition.condition)(context) {
-                return Some(transition);
-            }
-        }
-        None
+        self.transitions
+            .iter()
+            .find(|transition| (transition.condition)(context))
     }
 }
 
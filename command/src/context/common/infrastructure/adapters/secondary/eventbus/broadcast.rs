@@ -0,0 +1,242 @@
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use futures::{future::join_all, Stream};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::context::common::application::ports::outbound::event_bus::EventBus;
+use crate::context::common::domain::entity::{
+    aggregate::Aggregate, event::EventEnvelope, filter::EventFilter,
+};
+
+/// How many events a subscriber may fall behind by default.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// An `EventBus` fanning every event out to all subscriptions in the process.
+///
+/// Each subscription is a consumer group: `receive_events` starts a group of
+/// one, `receive_group` joins the named group or starts it. A group's members
+/// take turns, each event going to the next member with room in its buffer.
+/// Members filtered by `receive_filtered` are only handed the events their
+/// filter lets through; the bus skips the rest without copying them.
+///
+/// Every member has a bounded `tokio::sync::mpsc` buffer of its own rather
+/// than a slot in a shared `tokio::sync::broadcast` ring, so a slow member is
+/// never skipped past. Buffers hold `capacity` events. Once every member of a
+/// group is that far behind, `send_event` waits until one catches up, so a
+/// slow consumer slows down the sender instead of growing a queue; groups are
+/// handed the event at the same time, so a full group only holds up itself.
+///
+/// Subscriptions only see events sent after they were made. An event no
+/// subscription is interested in, because there are none or no filter lets
+/// it through, is dropped and the send succeeds. Streams end once the bus is
+/// dropped and they handed out what they buffered.
+pub struct BroadcastBus<T> {
+    capacity: usize,
+    groups: Mutex<Vec<Group<T>>>,
+}
+
+struct Group<T> {
+    // None for a subscription of its own
+    name: Option<String>,
+    members: Vec<Member<T>>,
+    // The member whose turn it is
+    next: usize,
+}
+
+struct Member<T> {
+    sender: mpsc::Sender<T>,
+    filter: EventFilter,
+}
+
+impl<T> Default for BroadcastBus<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> BroadcastBus<T> {
+    pub fn new() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            groups: Mutex::new(vec![]),
+        }
+    }
+
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    fn subscribe(
+        &self,
+        filter: EventFilter,
+        name: Option<&str>,
+    ) -> Pin<Box<dyn Stream<Item = T> + Send>>
+    where
+        T: Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel(self.capacity);
+        let member = Member { sender, filter };
+        // The lock guards no invariant a panicking holder could break.
+        let mut groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+        let joined = name.and_then(|x| groups.iter_mut().find(|y| y.name.as_deref() == Some(x)));
+        match joined {
+            Some(group) => group.members.push(member),
+            None => groups.push(Group {
+                name: name.map(|x| x.to_string()),
+                members: vec![member],
+                next: 0,
+            }),
+        }
+        Box::pin(ReceiverStream::new(receiver))
+    }
+}
+
+/// Hands `event` to one of `members`, starting with the one whose turn it is.
+/// A member with room takes it right away; when all are full it waits for
+/// that first one. Members that left are passed over.
+async fn deliver<T>(members: Vec<mpsc::Sender<T>>, mut event: T) {
+    for member in &members {
+        match member.try_send(event) {
+            Ok(()) => return,
+            Err(TrySendError::Full(x)) | Err(TrySendError::Closed(x)) => event = x,
+        }
+    }
+    for member in &members {
+        match member.send(event).await {
+            Ok(()) => return,
+            Err(mpsc::error::SendError(x)) => event = x,
+        }
+    }
+    // The whole group left since the event was sent, so nobody is missing it.
+}
+
+#[async_trait]
+impl<A: Aggregate + 'static> EventBus<EventEnvelope<A>, EventEnvelope<A>>
+    for BroadcastBus<EventEnvelope<A>>
+{
+    async fn send_event(&self, event: EventEnvelope<A>) -> Result<(), anyhow::Error> {
+        let targets: Vec<Vec<mpsc::Sender<EventEnvelope<A>>>> = {
+            let mut groups = self.groups.lock().unwrap_or_else(|e| e.into_inner());
+            for group in groups.iter_mut() {
+                group.members.retain(|x| !x.sender.is_closed());
+            }
+            groups.retain(|x| !x.members.is_empty());
+            groups
+                .iter_mut()
+                .filter_map(|group| {
+                    let mut members: Vec<_> = group
+                        .members
+                        .iter()
+                        .filter(|x| x.filter.matches(&event))
+                        .map(|x| x.sender.clone())
+                        .collect();
+                    if members.is_empty() {
+                        return None;
+                    }
+                    let start = group.next % members.len();
+                    group.next = start + 1;
+                    members.rotate_left(start);
+                    Some(members)
+                })
+                .collect()
+        };
+        join_all(
+            targets
+                .into_iter()
+                .map(|members| deliver(members, event.clone())),
+        )
+        .await;
+        Ok(())
+    }
+
+    async fn receive_events(&self) -> Pin<Box<dyn Stream<Item = EventEnvelope<A>> + Send>> {
+        self.subscribe(EventFilter::all(), None)
+    }
+
+    async fn receive_group(
+        &self,
+        group: &str,
+    ) -> Pin<Box<dyn Stream<Item = EventEnvelope<A>> + Send>> {
+        self.subscribe(EventFilter::all(), Some(group))
+    }
+
+    async fn receive_filtered(
+        &self,
+        filter: EventFilter,
+        group: Option<&str>,
+    ) -> Pin<Box<dyn Stream<Item = EventEnvelope<A>> + Send>> {
+        self.subscribe(filter, group)
+    }
+}
+
+#[cfg(test)]
+mod broadcast_test {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+
+    use crate::context::common::application::ports::outbound::event_bus::EventBus;
+    use crate::context::common::domain::entity::{event::EventEnvelope, filter::EventFilter};
+    use crate::context::prescription::domain::entity::{
+        aggregate::PrescriptionAggregate, fixture::event,
+    };
+
+    use super::BroadcastBus;
+
+    async fn versions<S>(events: S) -> Vec<i64>
+    where
+        S: futures::Stream<Item = EventEnvelope<PrescriptionAggregate>>,
+    {
+        events.map(|x| x.version).collect().await
+    }
+
+    #[tokio::test]
+    async fn subscribers_each_see_every_event_and_groups_share_them() {
+        let bus = BroadcastBus::new();
+        bus.send_event(event("1", 1)).await.unwrap();
+
+        let audit = bus.receive_events().await;
+        let first = bus.receive_group("mailer").await;
+        let second = bus.receive_group("mailer").await;
+        let updates = bus
+            .receive_filtered(
+                EventFilter::all().with_topic("Prescription.PrescriptionUpdated"),
+                None,
+            )
+            .await;
+        for x in 1..=4 {
+            bus.send_event(event("1", x)).await.unwrap();
+        }
+        drop(bus);
+
+        assert_eq!(versions(audit).await, vec![1, 2, 3, 4]);
+        assert_eq!(versions(updates).await, vec![2, 3, 4]);
+        let mut shared = versions(first).await;
+        shared.extend(versions(second).await);
+        shared.sort();
+        assert_eq!(shared, vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn sends_wait_for_a_full_subscriber_to_catch_up() {
+        let bus = BroadcastBus::new().with_capacity(1);
+        let mut events = bus.receive_events().await;
+        let mut other = bus.receive_group("other").await;
+        bus.send_event(event("1", 1)).await.unwrap();
+        assert_eq!(other.next().await.map(|x| x.version), Some(1));
+
+        let blocked =
+            tokio::time::timeout(Duration::from_millis(50), bus.send_event(event("1", 2))).await;
+        assert!(blocked.is_err());
+        // The full subscription only holds up the send, not the other group.
+        assert_eq!(other.next().await.map(|x| x.version), Some(2));
+
+        assert_eq!(events.next().await.map(|x| x.version), Some(1));
+        bus.send_event(event("1", 3)).await.unwrap();
+        assert_eq!(events.next().await.map(|x| x.version), Some(3));
+    }
+}
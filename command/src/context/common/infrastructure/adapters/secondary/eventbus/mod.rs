@@ -1 +1 @@
-pub mod channel;
+pub mod broadcast;
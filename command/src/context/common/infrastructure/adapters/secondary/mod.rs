@@ -1,2 +1,3 @@
 pub mod eventbus;
+pub mod signal;
 pub mod storage;
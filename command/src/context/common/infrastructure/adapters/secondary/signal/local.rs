@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::Notify;
+
+use crate::context::common::application::ports::outbound::outbox_signal::OutboxSignal;
+
+/// An `OutboxSignal` within a single process.
+///
+/// Wakeups are coalesced: any number of notifications before the relay waits
+/// again wake it once, and one that arrives while it is busy is not lost.
+#[derive(Default)]
+pub struct LocalOutboxSignal {
+    notify: Notify,
+}
+
+impl LocalOutboxSignal {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+}
+
+#[async_trait]
+impl OutboxSignal for LocalOutboxSignal {
+    fn notify(&self) {
+        self.notify.notify_one();
+    }
+
+    async fn notified(&self) {
+        self.notify.notified().await;
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use sqlx::postgres::PgListener;
+
+use crate::context::common::application::ports::outbound::outbox_signal::OutboxSignal;
+use crate::context::common::infrastructure::adapters::secondary::storage::postgres::PostgresConnector;
+
+/// The channel the Postgres event store notifies when it commits outbox
+/// entries.
+pub const OUTBOX_CHANNEL: &str = "outbox_events";
+
+/// How long to wait before listening again after the connection failed.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Forwards the notifications Postgres sends on every outbox commit to a
+/// signal, so that a relay also wakes up for entries committed by other
+/// instances.
+pub struct PostgresOutboxListener {
+    connector: Arc<PostgresConnector>,
+    signal: Arc<dyn OutboxSignal + Send + Sync>,
+}
+
+impl PostgresOutboxListener {
+    pub fn new(
+        connector: Arc<PostgresConnector>,
+        signal: Arc<dyn OutboxSignal + Send + Sync>,
+    ) -> Self {
+        Self { connector, signal }
+    }
+
+    /// Listens until the process exits. Notifications sent while the
+    /// connection is down are lost, so every reconnect wakes the relay once to
+    /// pick up what it may have missed.
+    pub async fn run(self) {
+        loop {
+            if let Err(e) = self.listen().await {
+                println!("Outbox listener failed: {:?}", e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+            self.signal.notify();
+        }
+    }
+
+    async fn listen(&self) -> Result<(), anyhow::Error> {
+        let mut listener = PgListener::connect_with(&self.connector.pool).await?;
+        listener.listen(OUTBOX_CHANNEL).await?;
+        loop {
+            listener.recv().await?;
+            self.signal.notify();
+        }
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt::Debug;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+
+use crate::context::common::{
+    domain::entity::{aggregate::Aggregate, error::EventStoreError, event::EventEnvelope},
+    infrastructure::{dtos::storage::sql::SQLEventEnvelope, upcaster::UpcasterRegistry},
+};
+
+pub mod postgres;
+pub mod sqlite;
+
+const EVENT_TABLE_NAME: &str = "events";
+const SNAPSHOT_TABLE_NAME: &str = "snapshots";
+const OUTBOX_TABLE_NAME: &str = "outbox_events";
+const DEAD_LETTER_TABLE_NAME: &str = "outbox_dead_letters";
+
+const EVENT_FIELDS: [&str; 9] = [
+    "aggregate_type",
+    "aggregate_id",
+    "sequence",
+    "version",
+    "event_type",
+    "event_version",
+    "payload",
+    "metadata",
+    "timestamp",
+];
+
+const SNAPSHOT_FIELDS: [&str; 6] = [
+    "aggregate_type",
+    "aggregate_id",
+    "payload",
+    "version",
+    "snapshot_id",
+    "timestamp",
+];
+
+/// The serde representation a domain type `T` is persisted as.
+pub trait SqlPayload<T>:
+    Serialize + DeserializeOwned + Default + Debug + From<T> + Into<T> + Send + Sync + Unpin + 'static
+{
+}
+
+impl<T, P> SqlPayload<T> for P where
+    P: Serialize
+        + DeserializeOwned
+        + Default
+        + Debug
+        + From<T>
+        + Into<T>
+        + Send
+        + Sync
+        + Unpin
+        + 'static
+{
+}
+
+/// An `EventRepository` for any aggregate `A` on top of the SQL connector `C`.
+///
+/// Events are stored as `E` and snapshots as `S`, and every row is keyed by
+/// `A::aggregate_type()`, so several aggregates can share the same tables.
+pub struct SqlEventStore<C, A, E, S> {
+    connector: Arc<C>,
+    upcasters: Arc<UpcasterRegistry>,
+    _types: PhantomData<(A, E, S)>,
+}
+
+impl<C, A, E, S> SqlEventStore<C, A, E, S> {
+    pub fn new(connector: Arc<C>) -> Self {
+        Self {
+            connector,
+            upcasters: Arc::new(UpcasterRegistry::new()),
+            _types: PhantomData,
+        }
+    }
+
+    /// Upcast stored payloads with `upcasters` before decoding them as `E`.
+    pub fn with_upcasters(mut self, upcasters: UpcasterRegistry) -> Self {
+        self.upcasters = Arc::new(upcasters);
+        self
+    }
+}
+
+impl<C, A, E, S> SqlEventStore<C, A, E, S>
+where
+    A: Aggregate,
+    E: SqlPayload<A::Event>,
+{
+    /// Brings a stored row up to the current event shape and decodes it.
+    fn decode(&self, row: SQLEventEnvelope<Value>) -> Result<EventEnvelope<A>, EventStoreError> {
+        let (event_version, payload) =
+            self.upcasters
+                .upcast(&row.event_type, &row.event_version, row.payload.0)?;
+        let payload: E =
+            serde_json::from_value(payload).map_err(|e| EventStoreError::EventDecode {
+                event_type: row.event_type.clone(),
+                event_version: row.event_version.clone(),
+                source: e.into(),
+            })?;
+        let row = SQLEventEnvelope {
+            aggregate_id: row.aggregate_id,
+            aggregate_type: row.aggregate_type,
+            sequence: row.sequence,
+            version: row.version,
+            position: row.position,
+            event_type: row.event_type,
+            event_version,
+            payload: Json(payload),
+            metadata: row.metadata,
+            timestamp: row.timestamp,
+        };
+        Ok(row.into())
+    }
+}
+
+/// The columns read back for an event: everything that is inserted plus the
+/// position the store assigned.
+fn event_columns() -> String {
+    format!("{}, position", EVENT_FIELDS.join(", "))
+}
+
+fn placeholders(count: usize, prefix: &str) -> String {
+    let placeholders: Vec<String> = (0..count).map(|x| format!("{}{}", prefix, x + 1)).collect();
+    placeholders.join(", ")
+}
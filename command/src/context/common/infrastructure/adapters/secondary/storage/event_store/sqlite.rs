@@ -0,0 +1,568 @@
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+use sqlx::{Sqlite, Transaction};
+
+use crate::context::common::{
+    application::ports::outbound::{event_bus::EventBus, event_repository::EventRepository},
+    domain::entity::{
+        aggregate::Aggregate,
+        error::EventStoreError,
+        event::{AggregateSnapshot, DeadLetter, DomainEvent, EventEnvelope, OutboxDelivery},
+    },
+    infrastructure::{
+        adapters::secondary::storage::sqlite::{is_write_conflict, SqliteConnector},
+        dtos::storage::sql::{SQLAggregateSnapshot, SQLDeadLetter, SQLEventEnvelope},
+    },
+};
+
+use super::{
+    event_columns, placeholders, SqlEventStore, SqlPayload, DEAD_LETTER_TABLE_NAME, EVENT_FIELDS,
+    EVENT_TABLE_NAME, OUTBOX_TABLE_NAME, SNAPSHOT_FIELDS, SNAPSHOT_TABLE_NAME,
+};
+
+async fn stream_version(
+    tx: &mut Transaction<'_, Sqlite>,
+    aggregate_type: &str,
+    aggregate_id: &str,
+) -> Result<i64, sqlx::Error> {
+    let query = format!(
+        "SELECT COALESCE(MAX(version), 0) FROM {} WHERE aggregate_type = ?1 AND aggregate_id = ?2",
+        EVENT_TABLE_NAME
+    );
+    let (version,): (i64,) = sqlx::query_as(&query)
+        .bind(aggregate_type)
+        .bind(aggregate_id)
+        .fetch_one(tx)
+        .await?;
+    Ok(version)
+}
+
+#[async_trait]
+impl<A, E, S>
+    EventRepository<EventEnvelope<A>, EventEnvelope<A>, AggregateSnapshot<A>, AggregateSnapshot<A>>
+    for SqlEventStore<SqliteConnector, A, E, S>
+where
+    A: Aggregate + 'static,
+    E: SqlPayload<A::Event>,
+    S: SqlPayload<A>,
+{
+    async fn store_events(
+        &self,
+        events: Vec<EventEnvelope<A>>,
+        expected_version: i64,
+    ) -> Result<(), anyhow::Error> {
+        // Positions are handed out while the row is written, so they are dense
+        // and follow commit order.
+        let query = format!(
+            "INSERT INTO {table} ({}) SELECT {}, COALESCE(MAX(position), 0) + 1 FROM {table} RETURNING position",
+            event_columns(),
+            placeholders(EVENT_FIELDS.len(), "?"),
+            table = EVENT_TABLE_NAME
+        );
+        let outbox_query = format!(
+            "INSERT INTO {} ({}) VALUES ( {} )",
+            OUTBOX_TABLE_NAME,
+            event_columns(),
+            placeholders(EVENT_FIELDS.len() + 1, "?")
+        );
+        let aggregate_id = match events.first() {
+            Some(x) => x.aggregate_id.clone(),
+            None => return Ok(()),
+        };
+        // Every event of the batch and its outbox row commit together or not at
+        // all; dropping `tx` on an early return rolls everything back.
+        let mut tx = self.connector.pool.begin().await?;
+        let actual = stream_version(&mut tx, &A::aggregate_type(), &aggregate_id).await?;
+        if actual != expected_version {
+            return Err(EventStoreError::ConcurrencyConflict {
+                aggregate_id,
+                expected: expected_version,
+                actual,
+            }
+            .into());
+        }
+        for x in events {
+            let payload = json!(E::from(x.payload.clone())).to_string();
+            let insert: Result<(i64,), sqlx::Error> = sqlx::query_as(&query)
+                .bind(A::aggregate_type())
+                .bind(&x.aggregate_id)
+                .bind(&x.sequence)
+                .bind(x.version)
+                .bind(x.payload.event_type())
+                .bind(x.payload.event_version())
+                .bind(&payload)
+                .bind(json!(x.metadata).to_string())
+                .bind(x.timestamp.to_rfc3339())
+                .fetch_one(&mut tx)
+                .await;
+            let position = match insert {
+                Err(e) if is_write_conflict(&e) => {
+                    let actual =
+                        stream_version(&mut tx, &A::aggregate_type(), &aggregate_id).await?;
+                    return Err(EventStoreError::ConcurrencyConflict {
+                        aggregate_id,
+                        expected: expected_version,
+                        actual,
+                    }
+                    .into());
+                }
+                Err(e) => {
+                    return Err(EventStoreError::EventAppend {
+                        aggregate_id,
+                        version: x.version,
+                        source: e.into(),
+                    }
+                    .into())
+                }
+                Ok((x,)) => x,
+            };
+            let outbox_insert = sqlx::query::<Sqlite>(&outbox_query)
+                .bind(A::aggregate_type())
+                .bind(&x.aggregate_id)
+                .bind(&x.sequence)
+                .bind(x.version)
+                .bind(x.payload.event_type())
+                .bind(x.payload.event_version())
+                .bind(&payload)
+                .bind(json!(x.metadata).to_string())
+                .bind(x.timestamp.to_rfc3339())
+                .bind(position)
+                .execute(&mut tx)
+                .await;
+            if let Err(e) = outbox_insert {
+                return Err(EventStoreError::OutboxAppend {
+                    aggregate_id,
+                    version: x.version,
+                    source: e.into(),
+                }
+                .into());
+            }
+        }
+        tx.commit().await.map_err(|e| EventStoreError::Commit {
+            aggregate_id,
+            source: e.into(),
+        })?;
+        Ok(())
+    }
+
+    async fn retrieve_events(
+        &self,
+        aggregate_id: String,
+        after: Option<i64>,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        let query = match after {
+            None => format!(
+                "SELECT {} FROM {} WHERE aggregate_type = ?1 AND aggregate_id = ?2 ORDER BY version ASC",
+                event_columns(),
+                EVENT_TABLE_NAME
+            ),
+            Some(_) => format!(
+                "SELECT {} FROM {} WHERE aggregate_type = ?1 AND aggregate_id = ?2 AND version > ?3 ORDER BY version ASC",
+                event_columns(),
+                EVENT_TABLE_NAME
+            ),
+        };
+        let mut plan = sqlx::query_as::<Sqlite, SQLEventEnvelope<Value>>(&query)
+            .bind(A::aggregate_type())
+            .bind(aggregate_id);
+        if let Some(x) = after {
+            plan = plan.bind(x);
+        }
+        let results = plan.fetch_all(&self.connector.pool).await?;
+        results
+            .into_iter()
+            .map(|x| self.decode(x).map_err(|e| e.into()))
+            .collect()
+    }
+
+    async fn retrieve_all_events(
+        &self,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE aggregate_type = ?1 AND position > ?2 ORDER BY position ASC LIMIT ?3",
+            event_columns(),
+            EVENT_TABLE_NAME
+        );
+        let results = sqlx::query_as::<Sqlite, SQLEventEnvelope<Value>>(&query)
+            .bind(A::aggregate_type())
+            .bind(after)
+            .bind(limit)
+            .fetch_all(&self.connector.pool)
+            .await?;
+        results
+            .into_iter()
+            .map(|x| self.decode(x).map_err(|e| e.into()))
+            .collect()
+    }
+
+    async fn store_snapshot(&self, snapshot: AggregateSnapshot<A>) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} ({}) VALUES ( {} )",
+            SNAPSHOT_TABLE_NAME,
+            SNAPSHOT_FIELDS.join(", "),
+            placeholders(SNAPSHOT_FIELDS.len(), "?")
+        );
+        let aggregate_id = snapshot.aggregate_id.clone();
+        sqlx::query::<Sqlite>(&query)
+            .bind(A::aggregate_type())
+            .bind(snapshot.aggregate_id)
+            .bind(json!(S::from(snapshot.payload)).to_string())
+            .bind(snapshot.version)
+            .bind(snapshot.snapshot_id)
+            .bind(snapshot.timestamp)
+            .execute(&self.connector.pool)
+            .await
+            .map_err(|e| EventStoreError::SnapshotAppend {
+                aggregate_id,
+                source: e.into(),
+            })?;
+        Ok(())
+    }
+
+    async fn retrieve_latest_snapshot(
+        &self,
+        aggregate_id: String,
+    ) -> Result<Option<AggregateSnapshot<A>>, anyhow::Error> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE aggregate_type = ?1 AND aggregate_id = ?2 ORDER BY version DESC, snapshot_id DESC LIMIT 1",
+            SNAPSHOT_FIELDS.join(", "),
+            SNAPSHOT_TABLE_NAME
+        );
+        let result = sqlx::query_as::<Sqlite, SQLAggregateSnapshot<S>>(&query)
+            .bind(A::aggregate_type())
+            .bind(aggregate_id)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        Ok(result.map(|x| x.into()))
+    }
+
+    async fn prune_snapshots(
+        &self,
+        keep_last: i64,
+        taken_before: Option<DateTime<Utc>>,
+    ) -> Result<u64, anyhow::Error> {
+        let query = format!(
+            "DELETE FROM {table} WHERE snapshot_id IN ( \
+             SELECT snapshot_id FROM ( \
+             SELECT snapshot_id, timestamp, ROW_NUMBER() OVER (PARTITION BY aggregate_id ORDER BY version DESC, snapshot_id DESC) AS newest \
+             FROM {table} WHERE aggregate_type = ?1 \
+             ) ranked WHERE newest > ?2 AND (?3 IS NULL OR timestamp < ?3) )",
+            table = SNAPSHOT_TABLE_NAME
+        );
+        let result = sqlx::query::<Sqlite>(&query)
+            .bind(A::aggregate_type())
+            .bind(keep_last)
+            .bind(taken_before)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn delete_snapshots(&self) -> Result<u64, anyhow::Error> {
+        let query = format!(
+            "DELETE FROM {} WHERE aggregate_type = ?1",
+            SNAPSHOT_TABLE_NAME
+        );
+        let result = sqlx::query::<Sqlite>(&query)
+            .bind(A::aggregate_type())
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn retrieve_aggregate_ids(&self) -> Result<Vec<String>, anyhow::Error> {
+        let query = format!(
+            "SELECT DISTINCT aggregate_id FROM {} WHERE aggregate_type = ?1 ORDER BY aggregate_id",
+            EVENT_TABLE_NAME
+        );
+        let results: Vec<(String,)> = sqlx::query_as(&query)
+            .bind(A::aggregate_type())
+            .fetch_all(&self.connector.pool)
+            .await?;
+        Ok(results.into_iter().map(|(x,)| x).collect())
+    }
+
+    async fn claim_outbox_events(
+        &self,
+        owner: &str,
+        limit: i64,
+        lease: Duration,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        // A single statement, so SQLite's write lock keeps concurrent claims
+        // from leasing different events of the same aggregate.
+        let query = format!(
+            "UPDATE {table} SET lease_owner = ?2, lease_expires_at = ?3 \
+             WHERE sequence IN ( \
+             SELECT sequence FROM {table} WHERE aggregate_type = ?1 AND aggregate_id NOT IN ( \
+             SELECT aggregate_id FROM {table} \
+             WHERE aggregate_type = ?1 \
+             AND ((lease_owner <> ?2 AND lease_expires_at > ?4) OR next_attempt_at > ?4) \
+             ) ORDER BY position LIMIT ?5 ) \
+             RETURNING {columns}",
+            table = OUTBOX_TABLE_NAME,
+            columns = event_columns()
+        );
+        let now = Utc::now();
+        let results = sqlx::query_as::<Sqlite, SQLEventEnvelope<Value>>(&query)
+            .bind(A::aggregate_type())
+            .bind(owner)
+            .bind(now + lease)
+            .bind(now)
+            .bind(limit)
+            .fetch_all(&self.connector.pool)
+            .await?;
+        let mut events = results
+            .into_iter()
+            .map(|x| self.decode(x).map_err(|e| e.into()))
+            .collect::<Result<Vec<EventEnvelope<A>>, anyhow::Error>>()?;
+        events.sort_by_key(|x| x.position);
+        Ok(events)
+    }
+
+    async fn release_outbox_events(&self, owner: &str) -> Result<u64, anyhow::Error> {
+        let query = format!(
+            "UPDATE {} SET lease_owner = NULL, lease_expires_at = NULL \
+             WHERE aggregate_type = ?1 AND lease_owner = ?2",
+            OUTBOX_TABLE_NAME
+        );
+        let result = sqlx::query::<Sqlite>(&query)
+            .bind(A::aggregate_type())
+            .bind(owner)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    async fn record_outbox_failure(
+        &self,
+        owner: &str,
+        event_id: &str,
+        error: &str,
+    ) -> Result<i64, anyhow::Error> {
+        let query = format!(
+            "UPDATE {} SET attempts = attempts + 1, last_error = ?3 \
+             WHERE sequence = ?1 AND lease_owner = ?2 RETURNING attempts",
+            OUTBOX_TABLE_NAME
+        );
+        let attempts: Option<(i64,)> = sqlx::query_as(&query)
+            .bind(event_id)
+            .bind(owner)
+            .bind(error)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        attempts
+            .map(|(x,)| x)
+            .ok_or_else(|| anyhow!("outbox event {} is not leased by {}", event_id, owner))
+    }
+
+    async fn defer_outbox_event(
+        &self,
+        owner: &str,
+        event_id: &str,
+        retry_at: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "UPDATE {} SET next_attempt_at = ?3 WHERE sequence = ?1 AND lease_owner = ?2",
+            OUTBOX_TABLE_NAME
+        );
+        sqlx::query::<Sqlite>(&query)
+            .bind(event_id)
+            .bind(owner)
+            .bind(retry_at)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn dead_letter_outbox_event(
+        &self,
+        owner: &str,
+        event_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        let insert_query = format!(
+            "INSERT INTO {dead} ({columns}, attempts, last_error, dead_lettered_at) \
+             SELECT {columns}, attempts, COALESCE(last_error, ''), ?3 FROM {outbox} \
+             WHERE sequence = ?1 AND lease_owner = ?2",
+            dead = DEAD_LETTER_TABLE_NAME,
+            outbox = OUTBOX_TABLE_NAME,
+            columns = event_columns()
+        );
+        let delete_query = format!(
+            "DELETE FROM {} WHERE sequence = ?1 AND lease_owner = ?2",
+            OUTBOX_TABLE_NAME
+        );
+        let mut tx = self.connector.pool.begin().await?;
+        sqlx::query::<Sqlite>(&insert_query)
+            .bind(event_id)
+            .bind(owner)
+            .bind(Utc::now())
+            .execute(&mut tx)
+            .await?;
+        sqlx::query::<Sqlite>(&delete_query)
+            .bind(event_id)
+            .bind(owner)
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn retrieve_dead_letters(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter<EventEnvelope<A>>>, anyhow::Error> {
+        let query = format!(
+            "SELECT {}, attempts, last_error, dead_lettered_at FROM {} \
+             WHERE aggregate_type = ?1 ORDER BY position LIMIT ?2",
+            event_columns(),
+            DEAD_LETTER_TABLE_NAME
+        );
+        let results = sqlx::query_as::<Sqlite, SQLDeadLetter<Value>>(&query)
+            .bind(A::aggregate_type())
+            .bind(limit)
+            .fetch_all(&self.connector.pool)
+            .await?;
+        results
+            .into_iter()
+            .map(|x| {
+                Ok(DeadLetter {
+                    event: self.decode(x.event)?,
+                    attempts: x.attempts,
+                    last_error: x.last_error,
+                    dead_lettered_at: x.dead_lettered_at,
+                })
+            })
+            .collect()
+    }
+
+    async fn requeue_dead_letters(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error> {
+        let insert_query = format!(
+            "INSERT INTO {outbox} ({columns}) SELECT {columns} FROM {dead} \
+             WHERE aggregate_type = ?1 AND (?2 IS NULL OR sequence = ?2)",
+            dead = DEAD_LETTER_TABLE_NAME,
+            outbox = OUTBOX_TABLE_NAME,
+            columns = event_columns()
+        );
+        let delete_query = format!(
+            "DELETE FROM {} WHERE aggregate_type = ?1 AND (?2 IS NULL OR sequence = ?2)",
+            DEAD_LETTER_TABLE_NAME
+        );
+        // `None` stands for every dead letter in both statements.
+        let ids = match event_ids {
+            Some(x) => x.into_iter().map(Some).collect(),
+            None => vec![None],
+        };
+        let mut tx = self.connector.pool.begin().await?;
+        let mut requeued = 0;
+        for id in ids {
+            requeued += sqlx::query::<Sqlite>(&insert_query)
+                .bind(A::aggregate_type())
+                .bind(&id)
+                .execute(&mut tx)
+                .await?
+                .rows_affected();
+            sqlx::query::<Sqlite>(&delete_query)
+                .bind(A::aggregate_type())
+                .bind(&id)
+                .execute(&mut tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(requeued)
+    }
+
+    async fn send_and_delete_outbox_event(
+        &self,
+        owner: &str,
+        event: EventEnvelope<A>,
+        bus: &Arc<dyn EventBus<EventEnvelope<A>, EventEnvelope<A>> + Send + Sync>,
+    ) -> Result<OutboxDelivery, anyhow::Error> {
+        // SQLite has a single writer, so holding the write lock across the
+        // send would stall consumers writing to this database while the bus
+        // waits for them to catch up. The lease taken by the claim guards the
+        // delete instead.
+        let sequence = event.sequence.clone();
+        bus.send_event(event).await?;
+        let query = format!(
+            "DELETE FROM {} WHERE sequence = ?1 AND lease_owner = ?2",
+            OUTBOX_TABLE_NAME
+        );
+        let deleted = sqlx::query::<Sqlite>(&query)
+            .bind(&sequence)
+            .bind(owner)
+            .execute(&self.connector.pool)
+            .await;
+        Ok(match deleted {
+            Ok(x) if x.rows_affected() == 1 => OutboxDelivery::Sent,
+            Ok(_) => OutboxDelivery::LeaseLost,
+            Err(e) => OutboxDelivery::NotDeleted(e.into()),
+        })
+    }
+
+    async fn retrieve_outbox_events(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        let query = format!(
+            "SELECT {} FROM {} WHERE aggregate_type = ?1 ORDER BY position LIMIT ?2",
+            event_columns(),
+            OUTBOX_TABLE_NAME
+        );
+        let results = sqlx::query_as::<Sqlite, SQLEventEnvelope<Value>>(&query)
+            .bind(A::aggregate_type())
+            .bind(limit)
+            .fetch_all(&self.connector.pool)
+            .await?;
+        results
+            .into_iter()
+            .map(|x| self.decode(x).map_err(|e| e.into()))
+            .collect()
+    }
+
+    async fn delete_outbox_events(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error> {
+        let mut tx = self.connector.pool.begin().await?;
+        let deleted = match event_ids {
+            None => {
+                let query = format!(
+                    "DELETE FROM {} WHERE aggregate_type = ?1",
+                    OUTBOX_TABLE_NAME
+                );
+                sqlx::query::<Sqlite>(&query)
+                    .bind(A::aggregate_type())
+                    .execute(&mut tx)
+                    .await?
+                    .rows_affected()
+            }
+            Some(ids) => {
+                let query = format!(
+                    "DELETE FROM {} WHERE aggregate_type = ?1 AND sequence = ?2",
+                    OUTBOX_TABLE_NAME
+                );
+                let mut deleted = 0;
+                for id in ids {
+                    deleted += sqlx::query::<Sqlite>(&query)
+                        .bind(A::aggregate_type())
+                        .bind(id)
+                        .execute(&mut tx)
+                        .await?
+                        .rows_affected();
+                }
+                deleted
+            }
+        };
+        tx.commit().await?;
+        Ok(deleted)
+    }
+}
@@ -0,0 +1,28 @@
+use std::sync::Arc;
+
+pub mod postgres;
+pub mod sqlite;
+
+const INBOX_TABLE_NAME: &str = "inbox_messages";
+
+/// An `Inbox` on top of the SQL connector `C`.
+///
+/// A message is recorded in the transaction `begin` hands out, which the
+/// consumer runs its own statements in, so the record and the side effects
+/// commit or roll back together, and only the first delivery of a message to
+/// commit runs.
+///
+/// How concurrent deliveries wait depends on the backend. On Postgres those
+/// of the same message wait for each other on the record's primary key. On
+/// SQLite the record's insert takes the database's single write lock and the
+/// transaction holds it until the consumer is done, so every other writer,
+/// including event appends and the outbox, waits for the whole handler.
+pub struct SqlInbox<C> {
+    connector: Arc<C>,
+}
+
+impl<C> SqlInbox<C> {
+    pub fn new(connector: Arc<C>) -> Self {
+        Self { connector }
+    }
+}
@@ -0,0 +1,56 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Postgres, Transaction};
+
+use crate::context::common::{
+    application::ports::outbound::inbox::{Inbox, InboxExpiry},
+    infrastructure::adapters::secondary::storage::postgres::PostgresConnector,
+};
+
+use super::{SqlInbox, INBOX_TABLE_NAME};
+
+#[async_trait]
+impl Inbox for SqlInbox<PostgresConnector> {
+    type Transaction = Transaction<'static, Postgres>;
+
+    async fn begin(
+        &self,
+        consumer: &str,
+        message_id: &str,
+    ) -> Result<Option<Self::Transaction>, anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (consumer, message_id, processed_at) VALUES ($1, $2, $3) \
+             ON CONFLICT (consumer, message_id) DO NOTHING",
+            INBOX_TABLE_NAME
+        );
+        let mut tx = self.connector.pool.begin().await?;
+        let inserted = sqlx::query::<Postgres>(&query)
+            .bind(consumer)
+            .bind(message_id)
+            .bind(Utc::now())
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        if inserted == 0 {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        Ok(Some(tx))
+    }
+
+    async fn commit(&self, transaction: Self::Transaction) -> Result<(), anyhow::Error> {
+        transaction.commit().await.map_err(|e| e.into())
+    }
+}
+
+#[async_trait]
+impl InboxExpiry for SqlInbox<PostgresConnector> {
+    async fn expire_messages(&self, before: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE processed_at < $1", INBOX_TABLE_NAME);
+        let result = sqlx::query::<Postgres>(&query)
+            .bind(before)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{Sqlite, Transaction};
+
+use crate::context::common::{
+    application::ports::outbound::inbox::{Inbox, InboxExpiry},
+    infrastructure::adapters::secondary::storage::sqlite::SqliteConnector,
+};
+
+use super::{SqlInbox, INBOX_TABLE_NAME};
+
+// The insert makes the transaction a writer, so it holds SQLite's write lock
+// until the consumer commits or drops it. Keep handlers short.
+#[async_trait]
+impl Inbox for SqlInbox<SqliteConnector> {
+    type Transaction = Transaction<'static, Sqlite>;
+
+    async fn begin(
+        &self,
+        consumer: &str,
+        message_id: &str,
+    ) -> Result<Option<Self::Transaction>, anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (consumer, message_id, processed_at) VALUES (?1, ?2, ?3) \
+             ON CONFLICT (consumer, message_id) DO NOTHING",
+            INBOX_TABLE_NAME
+        );
+        let mut tx = self.connector.pool.begin().await?;
+        let inserted = sqlx::query::<Sqlite>(&query)
+            .bind(consumer)
+            .bind(message_id)
+            .bind(Utc::now())
+            .execute(&mut tx)
+            .await?
+            .rows_affected();
+        if inserted == 0 {
+            tx.rollback().await?;
+            return Ok(None);
+        }
+        Ok(Some(tx))
+    }
+
+    async fn commit(&self, transaction: Self::Transaction) -> Result<(), anyhow::Error> {
+        transaction.commit().await.map_err(|e| e.into())
+    }
+}
+
+#[async_trait]
+impl InboxExpiry for SqlInbox<SqliteConnector> {
+    async fn expire_messages(&self, before: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE processed_at < ?1", INBOX_TABLE_NAME);
+        let result = sqlx::query::<Sqlite>(&query)
+            .bind(before)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod sqlite_test {
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::context::common::application::ports::outbound::inbox::{Inbox, InboxExpiry};
+    use crate::context::common::infrastructure::adapters::secondary::storage::{
+        migrations::migrate_sqlite, sqlite::SqliteConnector,
+    };
+
+    use super::SqlInbox;
+
+    async fn inbox() -> SqlInbox<SqliteConnector> {
+        // One connection, as every connection to `:memory:` is its own database
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        migrate_sqlite(&pool).await.unwrap();
+        SqlInbox::new(Arc::new(SqliteConnector { pool }))
+    }
+
+    #[tokio::test]
+    async fn messages_are_recorded_on_commit_and_forgotten_on_expiry() {
+        let inbox = inbox().await;
+
+        let dropped = inbox.begin("mailer", "1").await.unwrap();
+        assert!(dropped.is_some());
+        drop(dropped);
+        let transaction = inbox.begin("mailer", "1").await.unwrap().unwrap();
+        inbox.commit(transaction).await.unwrap();
+        assert!(inbox.begin("mailer", "1").await.unwrap().is_none());
+        let transaction = inbox.begin("auditor", "1").await.unwrap().unwrap();
+        inbox.commit(transaction).await.unwrap();
+
+        let expired = inbox
+            .expire_messages(Utc::now() + Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(expired, 2);
+        assert!(inbox.begin("mailer", "1").await.unwrap().is_some());
+    }
+}
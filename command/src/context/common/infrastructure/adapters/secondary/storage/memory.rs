@@ -0,0 +1,850 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::context::common::{
+    application::ports::outbound::{
+        checkpoint_store::CheckpointStore,
+        event_bus::EventBus,
+        event_repository::EventRepository,
+        inbox::{Inbox, InboxExpiry},
+        read_model::ReadModelRepository,
+    },
+    domain::entity::{
+        aggregate::Aggregate,
+        error::EventStoreError,
+        event::{AggregateSnapshot, DeadLetter, EventEnvelope, OutboxDelivery},
+    },
+};
+
+/// An `EventRepository` that keeps events, snapshots and the outbox in memory.
+///
+/// Meant for tests and prototyping: it mirrors the ordering and cursor
+/// semantics of the SQL adapters but nothing survives the process.
+pub struct InMemoryEventRepository<A: Aggregate + Clone> {
+    events: RwLock<Vec<EventEnvelope<A>>>,
+    snapshots: RwLock<Vec<AggregateSnapshot<A>>>,
+    outbox: RwLock<Vec<OutboxEntry<A>>>,
+    dead_letters: RwLock<Vec<DeadLetter<EventEnvelope<A>>>>,
+}
+
+/// An event waiting to be sent, with the owner and expiry of its lease and
+/// its failed sends so far.
+struct OutboxEntry<A: Aggregate> {
+    event: EventEnvelope<A>,
+    lease: Option<(String, DateTime<Utc>)>,
+    attempts: i64,
+    last_error: Option<String>,
+    next_attempt_at: Option<DateTime<Utc>>,
+}
+
+impl<A: Aggregate> OutboxEntry<A> {
+    fn new(event: EventEnvelope<A>) -> Self {
+        Self {
+            event,
+            lease: None,
+            attempts: 0,
+            last_error: None,
+            next_attempt_at: None,
+        }
+    }
+
+    fn leased_by(&self, owner: &str) -> bool {
+        matches!(&self.lease, Some((o, _)) if o == owner)
+    }
+}
+
+impl<A: Aggregate + Clone> InMemoryEventRepository<A> {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+}
+
+impl<A: Aggregate + Clone> Default for InMemoryEventRepository<A> {
+    fn default() -> Self {
+        Self {
+            events: RwLock::new(vec![]),
+            snapshots: RwLock::new(vec![]),
+            outbox: RwLock::new(vec![]),
+            dead_letters: RwLock::new(vec![]),
+        }
+    }
+}
+
+fn poisoned<T>(_: T) -> anyhow::Error {
+    anyhow!("in-memory event repository lock poisoned")
+}
+
+#[async_trait]
+impl<A: Aggregate + Clone>
+    EventRepository<EventEnvelope<A>, EventEnvelope<A>, AggregateSnapshot<A>, AggregateSnapshot<A>>
+    for InMemoryEventRepository<A>
+{
+    async fn store_events(
+        &self,
+        events: Vec<EventEnvelope<A>>,
+        expected_version: i64,
+    ) -> Result<(), anyhow::Error> {
+        let aggregate_id = match events.first() {
+            Some(x) => x.aggregate_id.clone(),
+            None => return Ok(()),
+        };
+        // Both locks are held for the whole append so the batch and its outbox
+        // entries become visible together.
+        let mut stored = self.events.write().map_err(poisoned)?;
+        let mut outbox = self.outbox.write().map_err(poisoned)?;
+        let actual = stored
+            .iter()
+            .filter(|x| x.aggregate_id == aggregate_id)
+            .map(|x| x.version)
+            .max()
+            .unwrap_or(0);
+        if actual != expected_version {
+            return Err(EventStoreError::ConcurrencyConflict {
+                aggregate_id,
+                expected: expected_version,
+                actual,
+            }
+            .into());
+        }
+        let first = stored.len() as i64 + 1;
+        let events: Vec<EventEnvelope<A>> = events
+            .into_iter()
+            .zip(first..)
+            .map(|(mut x, position)| {
+                x.position = Some(position);
+                x
+            })
+            .collect();
+        stored.extend(events.iter().cloned());
+        outbox.extend(events.into_iter().map(OutboxEntry::new));
+        Ok(())
+    }
+
+    async fn retrieve_events(
+        &self,
+        aggregate_id: String,
+        after: Option<i64>,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        let stored = self.events.read().map_err(poisoned)?;
+        let after = after.unwrap_or(0);
+        let mut resp: Vec<EventEnvelope<A>> = stored
+            .iter()
+            .filter(|x| x.aggregate_id == aggregate_id && x.version > after)
+            .cloned()
+            .collect();
+        resp.sort_by_key(|x| x.version);
+        Ok(resp)
+    }
+
+    async fn retrieve_all_events(
+        &self,
+        after: i64,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        let stored = self.events.read().map_err(poisoned)?;
+        Ok(stored
+            .iter()
+            .filter(|x| x.position.unwrap_or(0) > after)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn store_snapshot(&self, snapshot: AggregateSnapshot<A>) -> Result<(), anyhow::Error> {
+        self.snapshots.write().map_err(poisoned)?.push(snapshot);
+        Ok(())
+    }
+
+    async fn retrieve_latest_snapshot(
+        &self,
+        aggregate_id: String,
+    ) -> Result<Option<AggregateSnapshot<A>>, anyhow::Error> {
+        let snapshots = self.snapshots.read().map_err(poisoned)?;
+        Ok(snapshots
+            .iter()
+            .filter(|x| x.aggregate_id == aggregate_id)
+            .max_by(|a, b| (a.version, &a.snapshot_id).cmp(&(b.version, &b.snapshot_id)))
+            .cloned())
+    }
+
+    async fn prune_snapshots(
+        &self,
+        keep_last: i64,
+        taken_before: Option<DateTime<Utc>>,
+    ) -> Result<u64, anyhow::Error> {
+        let mut snapshots = self.snapshots.write().map_err(poisoned)?;
+        let mut newest_first: Vec<AggregateSnapshot<A>> = snapshots.drain(..).collect();
+        newest_first.sort_by(|a, b| (b.version, &b.snapshot_id).cmp(&(a.version, &a.snapshot_id)));
+        let mut seen: HashMap<String, i64> = HashMap::new();
+        let mut pruned = 0;
+        for snapshot in newest_first {
+            let rank = seen.entry(snapshot.aggregate_id.clone()).or_insert(0);
+            *rank += 1;
+            let expired = match taken_before {
+                Some(x) => snapshot.timestamp < x,
+                None => true,
+            };
+            if *rank > keep_last && expired {
+                pruned += 1;
+            } else {
+                snapshots.push(snapshot);
+            }
+        }
+        Ok(pruned)
+    }
+
+    async fn delete_snapshots(&self) -> Result<u64, anyhow::Error> {
+        let mut snapshots = self.snapshots.write().map_err(poisoned)?;
+        let deleted = snapshots.len() as u64;
+        snapshots.clear();
+        Ok(deleted)
+    }
+
+    async fn retrieve_aggregate_ids(&self) -> Result<Vec<String>, anyhow::Error> {
+        let stored = self.events.read().map_err(poisoned)?;
+        let mut ids: Vec<String> = stored.iter().map(|x| x.aggregate_id.clone()).collect();
+        ids.sort();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    async fn claim_outbox_events(
+        &self,
+        owner: &str,
+        limit: i64,
+        lease: Duration,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        let now = Utc::now();
+        let mut outbox = self.outbox.write().map_err(poisoned)?;
+        let taken: Vec<String> = outbox
+            .iter()
+            .filter(|x| {
+                matches!(&x.lease, Some((o, expires)) if o != owner && *expires > now)
+                    || x.next_attempt_at.is_some_and(|at| at > now)
+            })
+            .map(|x| x.event.aggregate_id.clone())
+            .collect();
+        Ok(outbox
+            .iter_mut()
+            .filter(|x| !taken.contains(&x.event.aggregate_id))
+            .take(limit.max(0) as usize)
+            .map(|x| {
+                x.lease = Some((owner.to_string(), now + lease));
+                x.event.clone()
+            })
+            .collect())
+    }
+
+    async fn release_outbox_events(&self, owner: &str) -> Result<u64, anyhow::Error> {
+        let mut released = 0;
+        for entry in self.outbox.write().map_err(poisoned)?.iter_mut() {
+            if entry.leased_by(owner) {
+                entry.lease = None;
+                released += 1;
+            }
+        }
+        Ok(released)
+    }
+
+    async fn record_outbox_failure(
+        &self,
+        owner: &str,
+        event_id: &str,
+        error: &str,
+    ) -> Result<i64, anyhow::Error> {
+        let mut outbox = self.outbox.write().map_err(poisoned)?;
+        let entry = outbox
+            .iter_mut()
+            .find(|x| x.event.sequence == event_id && x.leased_by(owner))
+            .ok_or_else(|| anyhow!("outbox event {} is not leased by {}", event_id, owner))?;
+        entry.attempts += 1;
+        entry.last_error = Some(error.to_string());
+        Ok(entry.attempts)
+    }
+
+    async fn defer_outbox_event(
+        &self,
+        owner: &str,
+        event_id: &str,
+        retry_at: DateTime<Utc>,
+    ) -> Result<(), anyhow::Error> {
+        for entry in self.outbox.write().map_err(poisoned)?.iter_mut() {
+            if entry.event.sequence == event_id && entry.leased_by(owner) {
+                entry.next_attempt_at = Some(retry_at);
+            }
+        }
+        Ok(())
+    }
+
+    async fn dead_letter_outbox_event(
+        &self,
+        owner: &str,
+        event_id: &str,
+    ) -> Result<(), anyhow::Error> {
+        let mut outbox = self.outbox.write().map_err(poisoned)?;
+        let mut dead_letters = self.dead_letters.write().map_err(poisoned)?;
+        if let Some(index) = outbox
+            .iter()
+            .position(|x| x.event.sequence == event_id && x.leased_by(owner))
+        {
+            let entry = outbox.remove(index);
+            dead_letters.push(DeadLetter {
+                event: entry.event,
+                attempts: entry.attempts,
+                last_error: entry.last_error.unwrap_or_default(),
+                dead_lettered_at: Utc::now(),
+            });
+        }
+        Ok(())
+    }
+
+    async fn retrieve_dead_letters(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter<EventEnvelope<A>>>, anyhow::Error> {
+        let mut dead_letters = self.dead_letters.read().map_err(poisoned)?.clone();
+        dead_letters.sort_by_key(|x| x.event.position);
+        dead_letters.truncate(limit.max(0) as usize);
+        Ok(dead_letters)
+    }
+
+    async fn requeue_dead_letters(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error> {
+        let mut outbox = self.outbox.write().map_err(poisoned)?;
+        let mut dead_letters = self.dead_letters.write().map_err(poisoned)?;
+        let (requeued, kept): (Vec<_>, Vec<_>) =
+            dead_letters.drain(..).partition(|x| match &event_ids {
+                Some(ids) => ids.contains(&x.event.sequence),
+                None => true,
+            });
+        *dead_letters = kept;
+        let count = requeued.len() as u64;
+        outbox.extend(requeued.into_iter().map(|x| OutboxEntry::new(x.event)));
+        outbox.sort_by_key(|x| x.event.position);
+        Ok(count)
+    }
+
+    async fn send_and_delete_outbox_event(
+        &self,
+        owner: &str,
+        event: EventEnvelope<A>,
+        bus: &Arc<dyn EventBus<EventEnvelope<A>, EventEnvelope<A>> + Send + Sync>,
+    ) -> Result<OutboxDelivery, anyhow::Error> {
+        let sequence = event.sequence.clone();
+        bus.send_event(event).await?;
+        let mut outbox = self.outbox.write().map_err(poisoned)?;
+        let before = outbox.len();
+        outbox.retain(|x| !(x.event.sequence == sequence && x.leased_by(owner)));
+        if outbox.len() == before {
+            return Ok(OutboxDelivery::LeaseLost);
+        }
+        Ok(OutboxDelivery::Sent)
+    }
+
+    async fn retrieve_outbox_events(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope<A>>, anyhow::Error> {
+        let outbox = self.outbox.read().map_err(poisoned)?;
+        Ok(outbox
+            .iter()
+            .take(limit.max(0) as usize)
+            .map(|x| x.event.clone())
+            .collect())
+    }
+
+    async fn delete_outbox_events(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error> {
+        let mut outbox = self.outbox.write().map_err(poisoned)?;
+        let before = outbox.len();
+        match event_ids {
+            None => outbox.clear(),
+            Some(ids) => outbox.retain(|x| !ids.contains(&x.event.sequence)),
+        }
+        Ok((before - outbox.len()) as u64)
+    }
+}
+
+/// A `CheckpointStore` that keeps projection checkpoints in memory.
+#[derive(Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: RwLock<HashMap<(String, String), i64>>,
+    positions: RwLock<HashMap<String, i64>>,
+}
+
+impl InMemoryCheckpointStore {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for InMemoryCheckpointStore {
+    async fn load_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+    ) -> Result<i64, anyhow::Error> {
+        let checkpoints = self.checkpoints.read().map_err(poisoned)?;
+        Ok(*checkpoints
+            .get(&(projection.to_string(), aggregate_id.to_string()))
+            .unwrap_or(&0))
+    }
+
+    async fn save_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+        version: i64,
+    ) -> Result<(), anyhow::Error> {
+        self.checkpoints
+            .write()
+            .map_err(poisoned)?
+            .insert((projection.to_string(), aggregate_id.to_string()), version);
+        Ok(())
+    }
+
+    async fn reset_checkpoints(&self, projection: &str) -> Result<(), anyhow::Error> {
+        self.checkpoints
+            .write()
+            .map_err(poisoned)?
+            .retain(|(x, _), _| x != projection);
+        Ok(())
+    }
+
+    async fn load_position(&self, subscription: &str) -> Result<i64, anyhow::Error> {
+        let positions = self.positions.read().map_err(poisoned)?;
+        Ok(*positions.get(subscription).unwrap_or(&0))
+    }
+
+    async fn save_position(&self, subscription: &str, position: i64) -> Result<(), anyhow::Error> {
+        self.positions
+            .write()
+            .map_err(poisoned)?
+            .insert(subscription.to_string(), position);
+        Ok(())
+    }
+}
+
+/// An `Inbox` that keeps the processed message ids in memory.
+///
+/// Nothing but the record itself joins its transactions, and a message is
+/// only recorded on commit, so concurrent deliveries of the same message may
+/// both run.
+#[derive(Default)]
+pub struct InMemoryInbox {
+    processed: RwLock<HashMap<(String, String), DateTime<Utc>>>,
+}
+
+impl InMemoryInbox {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+}
+
+/// A message `InMemoryInbox::begin` will record once committed.
+pub struct InMemoryInboxTransaction {
+    consumer: String,
+    message_id: String,
+}
+
+#[async_trait]
+impl Inbox for InMemoryInbox {
+    type Transaction = InMemoryInboxTransaction;
+
+    async fn begin(
+        &self,
+        consumer: &str,
+        message_id: &str,
+    ) -> Result<Option<Self::Transaction>, anyhow::Error> {
+        let key = (consumer.to_string(), message_id.to_string());
+        if self.processed.read().map_err(poisoned)?.contains_key(&key) {
+            return Ok(None);
+        }
+        Ok(Some(InMemoryInboxTransaction {
+            consumer: key.0,
+            message_id: key.1,
+        }))
+    }
+
+    async fn commit(&self, transaction: Self::Transaction) -> Result<(), anyhow::Error> {
+        self.processed
+            .write()
+            .map_err(poisoned)?
+            .entry((transaction.consumer, transaction.message_id))
+            .or_insert_with(Utc::now);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl InboxExpiry for InMemoryInbox {
+    async fn expire_messages(&self, before: DateTime<Utc>) -> Result<u64, anyhow::Error> {
+        let mut processed = self.processed.write().map_err(poisoned)?;
+        let count = processed.len();
+        processed.retain(|_, x| *x >= before);
+        Ok((count - processed.len()) as u64)
+    }
+}
+
+/// A `ReadModelRepository` that keeps views in memory, ordered by id.
+pub struct InMemoryReadModelStore<V> {
+    views: RwLock<BTreeMap<String, V>>,
+    shadow: RwLock<Option<Arc<InMemoryReadModelStore<V>>>>,
+}
+
+impl<V> InMemoryReadModelStore<V> {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+}
+
+impl<V> Default for InMemoryReadModelStore<V> {
+    fn default() -> Self {
+        Self {
+            views: RwLock::new(BTreeMap::new()),
+            shadow: RwLock::new(None),
+        }
+    }
+}
+
+#[async_trait]
+impl<V> ReadModelRepository<V> for InMemoryReadModelStore<V>
+where
+    V: Serialize + Clone + Send + Sync + 'static,
+{
+    async fn load(&self, id: &str) -> Result<Option<V>, anyhow::Error> {
+        Ok(self.views.read().map_err(poisoned)?.get(id).cloned())
+    }
+
+    async fn save(&self, id: &str, _version: i64, view: V) -> Result<(), anyhow::Error> {
+        self.views
+            .write()
+            .map_err(poisoned)?
+            .insert(id.to_string(), view);
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), anyhow::Error> {
+        self.views.write().map_err(poisoned)?.remove(id);
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        filter: Vec<(String, String)>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<V>, anyhow::Error> {
+        let views = self.views.read().map_err(poisoned)?;
+        Ok(views
+            .values()
+            .filter(|x| {
+                let value = serde_json::to_value(x).unwrap_or_default();
+                filter.iter().all(|(field, expected)| match &value[field] {
+                    Value::String(x) => x == expected,
+                    x @ (Value::Number(_) | Value::Bool(_)) => &x.to_string() == expected,
+                    _ => false,
+                })
+            })
+            .skip(offset.max(0) as usize)
+            .take(limit.max(0) as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn create_shadow(
+        &self,
+    ) -> Result<Arc<dyn ReadModelRepository<V> + Send + Sync>, anyhow::Error> {
+        let shadow = InMemoryReadModelStore::new();
+        *self.shadow.write().map_err(poisoned)? = Some(shadow.clone());
+        Ok(shadow)
+    }
+
+    async fn swap_shadow(&self) -> Result<(), anyhow::Error> {
+        let shadow = self
+            .shadow
+            .write()
+            .map_err(poisoned)?
+            .take()
+            .ok_or_else(|| anyhow!("no shadow read model to swap in"))?;
+        let rebuilt = std::mem::take(&mut *shadow.views.write().map_err(poisoned)?);
+        *self.views.write().map_err(poisoned)? = rebuilt;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod memory_test {
+    use std::sync::Arc;
+
+    use chrono::{Duration, Utc};
+    use futures::StreamExt;
+
+    use crate::context::common::application::ports::outbound::{
+        event_bus::EventBus, event_repository::EventRepository, read_model::ReadModelRepository,
+    };
+    use crate::context::common::domain::entity::error::EventStoreError;
+    use crate::context::common::domain::entity::event::{
+        AggregateSnapshot, EventEnvelope, OutboxDelivery,
+    };
+    use crate::context::common::infrastructure::adapters::secondary::eventbus::broadcast::BroadcastBus;
+    use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+    use crate::context::prescription::domain::entity::fixture::updated;
+
+    use super::{InMemoryEventRepository, InMemoryReadModelStore};
+
+    // Sequences are picked so that they do not sort like versions.
+    fn envelope(sequence: &str, version: i64) -> EventEnvelope<PrescriptionAggregate> {
+        EventEnvelope {
+            sequence: sequence.into(),
+            ..updated("1234", version, "1234")
+        }
+    }
+
+    #[tokio::test]
+    async fn retrieve_events_after_cursor_returns_later_events_in_version_order() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        repository
+            .store_events(vec![envelope("a", 1), envelope("c", 2)], 0)
+            .await
+            .unwrap();
+        repository
+            .store_events(vec![envelope("b", 3)], 2)
+            .await
+            .unwrap();
+
+        let all = repository
+            .retrieve_events("1234".into(), None)
+            .await
+            .unwrap();
+        let after = repository
+            .retrieve_events("1234".into(), Some(1))
+            .await
+            .unwrap();
+
+        let all: Vec<String> = all.into_iter().map(|x| x.sequence).collect();
+        let after: Vec<String> = after.into_iter().map(|x| x.sequence).collect();
+        assert_eq!(all, vec!["a", "c", "b"]);
+        assert_eq!(after, vec!["c", "b"]);
+        assert_eq!(
+            repository.retrieve_outbox_events(10).await.unwrap().len(),
+            3
+        );
+
+        let page = repository.retrieve_all_events(1, 1).await.unwrap();
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].sequence, "c");
+        assert_eq!(page[0].position, Some(2));
+    }
+
+    #[tokio::test]
+    async fn store_events_rejects_stale_expected_version() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        repository
+            .store_events(vec![envelope("a", 1)], 0)
+            .await
+            .unwrap();
+
+        let result = repository.store_events(vec![envelope("b", 1)], 0).await;
+
+        let error = result.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<EventStoreError>(),
+            Some(EventStoreError::ConcurrencyConflict { actual: 1, .. })
+        ));
+        assert_eq!(
+            repository.retrieve_outbox_events(10).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn delete_outbox_events_drops_only_the_given_events() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        repository
+            .store_events(vec![envelope("a", 1), envelope("b", 2)], 0)
+            .await
+            .unwrap();
+
+        let deleted = repository
+            .delete_outbox_events(Some(vec!["a".into(), "z".into()]))
+            .await
+            .unwrap();
+
+        assert_eq!(deleted, 1);
+        let outbox = repository.retrieve_outbox_events(10).await.unwrap();
+        assert_eq!(outbox.len(), 1);
+        assert_eq!(outbox[0].sequence, "b");
+        assert_eq!(repository.delete_outbox_events(None).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn claimed_aggregates_stay_with_their_owner_until_the_lease_ends() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        repository
+            .store_events(vec![envelope("a", 1), envelope("b", 2)], 0)
+            .await
+            .unwrap();
+        let bus: Arc<
+            dyn EventBus<EventEnvelope<PrescriptionAggregate>, EventEnvelope<PrescriptionAggregate>>
+                + Send
+                + Sync,
+        > = Arc::new(BroadcastBus::new());
+        let mut events = bus.receive_events().await;
+
+        let first = repository
+            .claim_outbox_events("one", 1, Duration::seconds(30))
+            .await
+            .unwrap();
+        let second = repository
+            .claim_outbox_events("two", 10, Duration::seconds(30))
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].sequence, "a");
+        assert!(second.is_empty());
+
+        let expired = repository
+            .claim_outbox_events("one", 10, Duration::zero())
+            .await
+            .unwrap();
+        assert_eq!(expired.len(), 2);
+        let taken = repository
+            .claim_outbox_events("two", 10, Duration::seconds(30))
+            .await
+            .unwrap();
+        assert_eq!(taken.len(), 2);
+        let deleted = repository
+            .send_and_delete_outbox_event("one", expired[0].clone(), &bus)
+            .await
+            .unwrap();
+        // The bus took the event, but the row now belongs to "two".
+        assert!(matches!(deleted, OutboxDelivery::LeaseLost));
+        assert_eq!(
+            events.next().await.map(|x| x.sequence),
+            Some(expired[0].sequence.clone())
+        );
+        assert_eq!(
+            repository.retrieve_outbox_events(10).await.unwrap().len(),
+            2
+        );
+
+        assert_eq!(repository.release_outbox_events("two").await.unwrap(), 2);
+        let reclaimed = repository
+            .claim_outbox_events("one", 10, Duration::seconds(30))
+            .await
+            .unwrap();
+        assert_eq!(reclaimed.len(), 2);
+        let deleted = repository
+            .send_and_delete_outbox_event("one", reclaimed[0].clone(), &bus)
+            .await
+            .unwrap();
+        assert!(matches!(deleted, OutboxDelivery::Sent));
+        assert_eq!(
+            repository.retrieve_outbox_events(10).await.unwrap().len(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn prune_snapshots_keeps_newest_per_aggregate_and_young_snapshots() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        let now = Utc::now();
+        for (aggregate_id, snapshot_id, age) in [
+            ("1", "a", 30),
+            ("1", "b", 20),
+            ("1", "c", 1),
+            ("2", "d", 30),
+        ] {
+            repository
+                .store_snapshot(AggregateSnapshot {
+                    aggregate_id: aggregate_id.into(),
+                    aggregate_type: "Prescription".into(),
+                    payload: PrescriptionAggregate::default(),
+                    version: 1,
+                    snapshot_id: snapshot_id.into(),
+                    timestamp: now - Duration::minutes(age),
+                })
+                .await
+                .unwrap();
+        }
+
+        let pruned = repository
+            .prune_snapshots(1, Some(now - Duration::minutes(25)))
+            .await
+            .unwrap();
+
+        assert_eq!(pruned, 1);
+        let mut kept: Vec<String> = repository
+            .snapshots
+            .read()
+            .unwrap()
+            .iter()
+            .map(|x| x.snapshot_id.clone())
+            .collect();
+        kept.sort();
+        assert_eq!(kept, vec!["b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn query_matches_numbers_and_booleans_by_their_json_text() {
+        #[derive(Clone, serde::Serialize)]
+        struct Stock {
+            count: i64,
+            active: bool,
+        }
+        let views = InMemoryReadModelStore::new();
+        views
+            .save(
+                "a",
+                1,
+                Stock {
+                    count: 5,
+                    active: true,
+                },
+            )
+            .await
+            .unwrap();
+        views
+            .save(
+                "b",
+                1,
+                Stock {
+                    count: 7,
+                    active: false,
+                },
+            )
+            .await
+            .unwrap();
+
+        let found = views
+            .query(
+                vec![
+                    ("count".into(), "5".into()),
+                    ("active".into(), "true".into()),
+                ],
+                10,
+                0,
+            )
+            .await
+            .unwrap();
+        let missing = views
+            .query(vec![("active".into(), "1".into())], 10, 0)
+            .await
+            .unwrap();
+
+        assert_eq!(found.iter().map(|x| x.count).collect::<Vec<_>>(), vec![5]);
+        assert!(missing.is_empty());
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::HashSet;
+
+use sqlx::migrate::{Migrate, Migrator};
+use sqlx::{Database, PgPool, Pool, SqlitePool};
+
+// The service's schema: event store, outbox, read models, checkpoints and
+// inbox, embedded at compile time. Applied versions are tracked by sqlx in `_sqlx_migrations`.
+static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("./migrations/sqlite");
+static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("./migrations/postgres");
+
+/// Version and description of a migration applied by a run.
+pub type AppliedMigration = (i64, String);
+
+pub async fn migrate_sqlite(pool: &SqlitePool) -> Result<Vec<AppliedMigration>, anyhow::Error> {
+    migrate(&SQLITE_MIGRATOR, pool).await
+}
+
+pub async fn migrate_postgres(pool: &PgPool) -> Result<Vec<AppliedMigration>, anyhow::Error> {
+    migrate(&POSTGRES_MIGRATOR, pool).await
+}
+
+/// Runs the pending migrations of `migrator` and returns those it applied,
+/// oldest first.
+async fn migrate<DB>(
+    migrator: &Migrator,
+    pool: &Pool<DB>,
+) -> Result<Vec<AppliedMigration>, anyhow::Error>
+where
+    DB: Database,
+    DB::Connection: Migrate,
+{
+    let applied: HashSet<i64> = {
+        let mut conn = pool.acquire().await?;
+        conn.ensure_migrations_table().await?;
+        conn.list_applied_migrations()
+            .await?
+            .into_iter()
+            .map(|x| x.version)
+            .collect()
+    };
+    migrator.run(pool).await?;
+    Ok(migrator
+        .iter()
+        .filter(|x| !x.migration_type.is_down_migration() && !applied.contains(&x.version))
+        .map(|x| (x.version, x.description.to_string()))
+        .collect())
+}
@@ -1 +1,7 @@
+pub mod event_store;
+pub mod inbox;
+pub mod memory;
+pub mod migrations;
+pub mod postgres;
+pub mod projection;
 pub mod sqlite;
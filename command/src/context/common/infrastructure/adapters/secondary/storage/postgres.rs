@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use once_cell::sync::OnceCell;
+use sqlx::PgPool;
+
+use super::migrations::{migrate_postgres, AppliedMigration};
+
+#[derive(Debug)]
+pub struct PostgresConnector {
+    pub pool: PgPool,
+}
+
+static INSTANCE: OnceCell<Arc<PostgresConnector>> = OnceCell::new();
+
+impl PostgresConnector {
+    pub async fn new(pool: Result<PgPool>) -> Result<Arc<Self>> {
+        match INSTANCE.get() {
+            Some(x) => Ok(x.clone()),
+            None => match pool {
+                Ok(x) => {
+                    let ret = Arc::new(Self { pool: x });
+                    INSTANCE.set(ret.clone()).expect("failed to set singleton");
+                    Ok(ret)
+                }
+                Err(e) => Err(e),
+            },
+        }
+    }
+
+    /// Brings the schema up to date with the migrations embedded in the binary
+    /// and returns the ones it applied.
+    pub async fn migrate(&self) -> Result<Vec<AppliedMigration>> {
+        migrate_postgres(&self.pool).await
+    }
+}
+
+/// Whether `error` means another writer got to the same rows first: a
+/// unique_violation or a serialization_failure.
+pub fn is_write_conflict(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => matches!(e.code().as_deref(), Some("23505") | Some("40001")),
+        _ => false,
+    }
+}
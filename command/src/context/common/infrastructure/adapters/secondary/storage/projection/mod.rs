@@ -0,0 +1,70 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::context::common::domain::entity::error::ReadModelError;
+
+pub mod postgres;
+pub mod sqlite;
+
+const CHECKPOINT_TABLE_NAME: &str = "projection_checkpoints";
+const POSITION_TABLE_NAME: &str = "subscription_checkpoints";
+/// Appended to the name of a read model table, and of its indexes where the
+/// database can rename them, to get the shadow copy a rebuild writes to.
+const SHADOW_SUFFIX: &str = "_rebuild";
+
+/// The serde representation a read model is persisted as.
+pub trait SqlView: Serialize + DeserializeOwned + Send + Sync + Unpin + 'static {}
+
+impl<V> SqlView for V where V: Serialize + DeserializeOwned + Send + Sync + Unpin + 'static {}
+
+/// A `CheckpointStore` on top of the SQL connector `C`.
+pub struct SqlCheckpointStore<C> {
+    connector: Arc<C>,
+}
+
+impl<C> SqlCheckpointStore<C> {
+    pub fn new(connector: Arc<C>) -> Self {
+        Self { connector }
+    }
+}
+
+/// A `ReadModelRepository` keeping views of type `V` in their own table.
+///
+/// The table is created by a migration and has the columns `id` (primary
+/// key), `version` (the last event version folded into the view), `payload`
+/// (the view as JSON) and `updated_at`. Queries filter on top-level fields of
+/// the payload, so frequently filtered fields deserve an expression index.
+///
+/// Rebuilds write to a shadow table next to it that `swap_shadow` renames into
+/// place, indexes included.
+pub struct SqlReadModelStore<C, V> {
+    connector: Arc<C>,
+    table: String,
+    _types: PhantomData<V>,
+}
+
+impl<C, V> SqlReadModelStore<C, V> {
+    pub fn new(connector: Arc<C>, table: &str) -> Self {
+        Self {
+            connector,
+            table: table.to_string(),
+            _types: PhantomData,
+        }
+    }
+
+    fn shadow_table(&self) -> String {
+        format!("{}{}", self.table, SHADOW_SUFFIX)
+    }
+}
+
+/// Filter fields end up inside the SQL text, so only plain identifiers pass.
+fn checked_field(field: &str) -> Result<&str, ReadModelError> {
+    let valid = !field.is_empty() && field.chars().all(|x| x.is_ascii_alphanumeric() || x == '_');
+    if valid {
+        Ok(field)
+    } else {
+        Err(ReadModelError::InvalidField(field.to_string()))
+    }
+}
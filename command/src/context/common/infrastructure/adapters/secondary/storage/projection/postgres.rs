@@ -0,0 +1,251 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use sqlx::types::Json;
+use sqlx::Postgres;
+
+use crate::context::common::{
+    application::ports::outbound::{
+        checkpoint_store::CheckpointStore, read_model::ReadModelRepository,
+    },
+    infrastructure::adapters::secondary::storage::postgres::PostgresConnector,
+};
+
+use super::{
+    checked_field, SqlCheckpointStore, SqlReadModelStore, SqlView, CHECKPOINT_TABLE_NAME,
+    POSITION_TABLE_NAME, SHADOW_SUFFIX,
+};
+
+#[async_trait]
+impl CheckpointStore for SqlCheckpointStore<PostgresConnector> {
+    async fn load_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+    ) -> Result<i64, anyhow::Error> {
+        let query = format!(
+            "SELECT version FROM {} WHERE projection = $1 AND aggregate_id = $2",
+            CHECKPOINT_TABLE_NAME
+        );
+        let result: Option<(i64,)> = sqlx::query_as(&query)
+            .bind(projection)
+            .bind(aggregate_id)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        Ok(result.map(|(x,)| x).unwrap_or(0))
+    }
+
+    async fn save_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+        version: i64,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (projection, aggregate_id, version) VALUES ($1, $2, $3) \
+             ON CONFLICT (projection, aggregate_id) DO UPDATE SET version = excluded.version",
+            CHECKPOINT_TABLE_NAME
+        );
+        sqlx::query::<Postgres>(&query)
+            .bind(projection)
+            .bind(aggregate_id)
+            .bind(version)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn reset_checkpoints(&self, projection: &str) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "DELETE FROM {} WHERE projection = $1",
+            CHECKPOINT_TABLE_NAME
+        );
+        sqlx::query::<Postgres>(&query)
+            .bind(projection)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn load_position(&self, subscription: &str) -> Result<i64, anyhow::Error> {
+        let query = format!(
+            "SELECT position FROM {} WHERE subscription = $1",
+            POSITION_TABLE_NAME
+        );
+        let result: Option<(i64,)> = sqlx::query_as(&query)
+            .bind(subscription)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        Ok(result.map(|(x,)| x).unwrap_or(0))
+    }
+
+    async fn save_position(&self, subscription: &str, position: i64) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (subscription, position) VALUES ($1, $2) \
+             ON CONFLICT (subscription) DO UPDATE SET position = excluded.position",
+            POSITION_TABLE_NAME
+        );
+        sqlx::query::<Postgres>(&query)
+            .bind(subscription)
+            .bind(position)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Name and definition of every index of table `$1` except its primary key.
+const SECONDARY_INDEXES: &str = "SELECT i.relname::TEXT, pg_get_indexdef(i.oid) FROM pg_index x \
+     JOIN pg_class i ON i.oid = x.indexrelid \
+     WHERE x.indrelid = $1::regclass AND NOT x.indisprimary";
+
+#[async_trait]
+impl<V: SqlView> ReadModelRepository<V> for SqlReadModelStore<PostgresConnector, V> {
+    async fn load(&self, id: &str) -> Result<Option<V>, anyhow::Error> {
+        let query = format!("SELECT payload FROM {} WHERE id = $1", self.table);
+        let result: Option<(Json<V>,)> = sqlx::query_as(&query)
+            .bind(id)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        Ok(result.map(|(x,)| x.0))
+    }
+
+    async fn save(&self, id: &str, version: i64, view: V) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (id, version, payload, updated_at) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (id) DO UPDATE SET version = excluded.version, \
+             payload = excluded.payload, updated_at = excluded.updated_at",
+            self.table
+        );
+        sqlx::query::<Postgres>(&query)
+            .bind(id)
+            .bind(version)
+            .bind(Json(view))
+            .bind(Utc::now())
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE id = $1", self.table);
+        sqlx::query::<Postgres>(&query)
+            .bind(id)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        filter: Vec<(String, String)>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<V>, anyhow::Error> {
+        let mut conditions = vec!["1 = 1".to_string()];
+        for (i, (field, _)) in filter.iter().enumerate() {
+            conditions.push(format!(
+                "payload ->> '{}' = ${}",
+                checked_field(field)?,
+                i + 1
+            ));
+        }
+        let query = format!(
+            "SELECT payload FROM {} WHERE {} ORDER BY id LIMIT ${} OFFSET ${}",
+            self.table,
+            conditions.join(" AND "),
+            filter.len() + 1,
+            filter.len() + 2
+        );
+        let mut plan = sqlx::query_as::<Postgres, (Json<V>,)>(&query);
+        for (_, value) in filter {
+            plan = plan.bind(value);
+        }
+        let results = plan
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.connector.pool)
+            .await?;
+        Ok(results.into_iter().map(|(x,)| x.0).collect())
+    }
+
+    async fn create_shadow(
+        &self,
+    ) -> Result<Arc<dyn ReadModelRepository<V> + Send + Sync>, anyhow::Error> {
+        let shadow = self.shadow_table();
+        let mut tx = self.connector.pool.begin().await?;
+        sqlx::query::<Postgres>(&format!("DROP TABLE IF EXISTS {}", shadow))
+            .execute(&mut tx)
+            .await?;
+        sqlx::query::<Postgres>(&format!(
+            "CREATE TABLE {shadow} (LIKE {table} INCLUDING DEFAULTS INCLUDING CONSTRAINTS)",
+            shadow = shadow,
+            table = self.table
+        ))
+        .execute(&mut tx)
+        .await?;
+        sqlx::query::<Postgres>(&format!(
+            "ALTER TABLE {shadow} ADD CONSTRAINT {shadow}_pkey PRIMARY KEY (id)",
+            shadow = shadow
+        ))
+        .execute(&mut tx)
+        .await?;
+        // The shadow gets a copy of every secondary index of the live table,
+        // named with the shadow suffix so the swap only has to rename them.
+        let indexes: Vec<(String, String)> = sqlx::query_as(SECONDARY_INDEXES)
+            .bind(&self.table)
+            .fetch_all(&mut tx)
+            .await?;
+        for (name, definition) in indexes {
+            let (kind, using) = match (definition.find(" INDEX "), definition.find(" USING ")) {
+                (Some(kind), Some(using)) => (&definition[..kind], &definition[using..]),
+                _ => continue,
+            };
+            sqlx::query::<Postgres>(&format!(
+                "{} INDEX {}{} ON {}{}",
+                kind, name, SHADOW_SUFFIX, shadow, using
+            ))
+            .execute(&mut tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(Arc::new(SqlReadModelStore::<PostgresConnector, V>::new(
+            self.connector.clone(),
+            &shadow,
+        )))
+    }
+
+    async fn swap_shadow(&self) -> Result<(), anyhow::Error> {
+        let shadow = self.shadow_table();
+        let mut tx = self.connector.pool.begin().await?;
+        let indexes: Vec<(String, String)> = sqlx::query_as(SECONDARY_INDEXES)
+            .bind(&shadow)
+            .fetch_all(&mut tx)
+            .await?;
+        // DDL is transactional, so readers wait for the commit and then see
+        // the rebuilt table under the old name.
+        sqlx::query::<Postgres>(&format!("DROP TABLE {}", self.table))
+            .execute(&mut tx)
+            .await?;
+        sqlx::query::<Postgres>(&format!("ALTER TABLE {} RENAME TO {}", shadow, self.table))
+            .execute(&mut tx)
+            .await?;
+        sqlx::query::<Postgres>(&format!(
+            "ALTER TABLE {table} RENAME CONSTRAINT {shadow}_pkey TO {table}_pkey",
+            shadow = shadow,
+            table = self.table
+        ))
+        .execute(&mut tx)
+        .await?;
+        for (name, _) in indexes {
+            if let Some(original) = name.strip_suffix(SHADOW_SUFFIX) {
+                sqlx::query::<Postgres>(&format!("ALTER INDEX {} RENAME TO {}", name, original))
+                    .execute(&mut tx)
+                    .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
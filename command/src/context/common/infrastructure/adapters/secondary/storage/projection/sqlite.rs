@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+use serde_json::json;
+use sqlx::Sqlite;
+
+use crate::context::common::{
+    application::ports::outbound::{
+        checkpoint_store::CheckpointStore, read_model::ReadModelRepository,
+    },
+    infrastructure::adapters::secondary::storage::sqlite::SqliteConnector,
+};
+
+use super::{
+    checked_field, SqlCheckpointStore, SqlReadModelStore, SqlView, CHECKPOINT_TABLE_NAME,
+    POSITION_TABLE_NAME,
+};
+
+#[async_trait]
+impl CheckpointStore for SqlCheckpointStore<SqliteConnector> {
+    async fn load_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+    ) -> Result<i64, anyhow::Error> {
+        let query = format!(
+            "SELECT version FROM {} WHERE projection = ?1 AND aggregate_id = ?2",
+            CHECKPOINT_TABLE_NAME
+        );
+        let result: Option<(i64,)> = sqlx::query_as(&query)
+            .bind(projection)
+            .bind(aggregate_id)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        Ok(result.map(|(x,)| x).unwrap_or(0))
+    }
+
+    async fn save_checkpoint(
+        &self,
+        projection: &str,
+        aggregate_id: &str,
+        version: i64,
+    ) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (projection, aggregate_id, version) VALUES (?1, ?2, ?3) \
+             ON CONFLICT (projection, aggregate_id) DO UPDATE SET version = excluded.version",
+            CHECKPOINT_TABLE_NAME
+        );
+        sqlx::query::<Sqlite>(&query)
+            .bind(projection)
+            .bind(aggregate_id)
+            .bind(version)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn reset_checkpoints(&self, projection: &str) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "DELETE FROM {} WHERE projection = ?1",
+            CHECKPOINT_TABLE_NAME
+        );
+        sqlx::query::<Sqlite>(&query)
+            .bind(projection)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn load_position(&self, subscription: &str) -> Result<i64, anyhow::Error> {
+        let query = format!(
+            "SELECT position FROM {} WHERE subscription = ?1",
+            POSITION_TABLE_NAME
+        );
+        let result: Option<(i64,)> = sqlx::query_as(&query)
+            .bind(subscription)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        Ok(result.map(|(x,)| x).unwrap_or(0))
+    }
+
+    async fn save_position(&self, subscription: &str, position: i64) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (subscription, position) VALUES (?1, ?2) \
+             ON CONFLICT (subscription) DO UPDATE SET position = excluded.position",
+            POSITION_TABLE_NAME
+        );
+        sqlx::query::<Sqlite>(&query)
+            .bind(subscription)
+            .bind(position)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<V: SqlView> ReadModelRepository<V> for SqlReadModelStore<SqliteConnector, V> {
+    async fn load(&self, id: &str) -> Result<Option<V>, anyhow::Error> {
+        let query = format!("SELECT payload FROM {} WHERE id = ?1", self.table);
+        let result: Option<(String,)> = sqlx::query_as(&query)
+            .bind(id)
+            .fetch_optional(&self.connector.pool)
+            .await?;
+        match result {
+            Some((x,)) => Ok(Some(serde_json::from_str(&x)?)),
+            None => Ok(None),
+        }
+    }
+
+    async fn save(&self, id: &str, version: i64, view: V) -> Result<(), anyhow::Error> {
+        let query = format!(
+            "INSERT INTO {} (id, version, payload, updated_at) VALUES (?1, ?2, ?3, ?4) \
+             ON CONFLICT (id) DO UPDATE SET version = excluded.version, \
+             payload = excluded.payload, updated_at = excluded.updated_at",
+            self.table
+        );
+        sqlx::query::<Sqlite>(&query)
+            .bind(id)
+            .bind(version)
+            .bind(json!(view).to_string())
+            .bind(Utc::now().to_rfc3339())
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, id: &str) -> Result<(), anyhow::Error> {
+        let query = format!("DELETE FROM {} WHERE id = ?1", self.table);
+        sqlx::query::<Sqlite>(&query)
+            .bind(id)
+            .execute(&self.connector.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn query(
+        &self,
+        filter: Vec<(String, String)>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<V>, anyhow::Error> {
+        let mut conditions = vec!["1 = 1".to_string()];
+        for (i, (field, _)) in filter.iter().enumerate() {
+            // json_extract gives numbers as numbers and booleans as 1 / 0, so
+            // both are turned into their JSON text to compare with the value.
+            let path = format!("'$.{}'", checked_field(field)?);
+            conditions.push(format!(
+                "CASE json_type(payload, {path}) WHEN 'true' THEN 'true' WHEN 'false' THEN 'false' \
+                 ELSE CAST(json_extract(payload, {path}) AS TEXT) END = ?{}",
+                i + 1,
+                path = path
+            ));
+        }
+        let query = format!(
+            "SELECT payload FROM {} WHERE {} ORDER BY id LIMIT ?{} OFFSET ?{}",
+            self.table,
+            conditions.join(" AND "),
+            filter.len() + 1,
+            filter.len() + 2
+        );
+        let mut plan = sqlx::query_as::<Sqlite, (String,)>(&query);
+        for (_, value) in filter {
+            plan = plan.bind(value);
+        }
+        let results = plan
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(&self.connector.pool)
+            .await?;
+        results
+            .into_iter()
+            .map(|(x,)| serde_json::from_str(&x).map_err(|e| e.into()))
+            .collect()
+    }
+
+    async fn create_shadow(
+        &self,
+    ) -> Result<Arc<dyn ReadModelRepository<V> + Send + Sync>, anyhow::Error> {
+        let shadow = self.shadow_table();
+        let mut tx = self.connector.pool.begin().await?;
+        sqlx::query::<Sqlite>(&format!("DROP TABLE IF EXISTS {}", shadow))
+            .execute(&mut tx)
+            .await?;
+        let (definition,): (String,) =
+            sqlx::query_as("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = ?1")
+                .bind(&self.table)
+                .fetch_one(&mut tx)
+                .await?;
+        sqlx::query::<Sqlite>(&definition.replacen(&self.table, &shadow, 1))
+            .execute(&mut tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Arc::new(SqlReadModelStore::<SqliteConnector, V>::new(
+            self.connector.clone(),
+            &shadow,
+        )))
+    }
+
+    async fn swap_shadow(&self) -> Result<(), anyhow::Error> {
+        let mut tx = self.connector.pool.begin().await?;
+        // SQLite cannot rename indexes, so the live table's are recreated on
+        // the shadow once it took the table's name. Readers keep seeing the
+        // old table until the transaction commits.
+        let indexes: Vec<(String,)> = sqlx::query_as(
+            "SELECT sql FROM sqlite_master WHERE type = 'index' AND tbl_name = ?1 AND sql IS NOT NULL",
+        )
+        .bind(&self.table)
+        .fetch_all(&mut tx)
+        .await?;
+        sqlx::query::<Sqlite>(&format!("DROP TABLE {}", self.table))
+            .execute(&mut tx)
+            .await?;
+        sqlx::query::<Sqlite>(&format!(
+            "ALTER TABLE {} RENAME TO {}",
+            self.shadow_table(),
+            self.table
+        ))
+        .execute(&mut tx)
+        .await?;
+        for (definition,) in indexes {
+            sqlx::query::<Sqlite>(&definition).execute(&mut tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
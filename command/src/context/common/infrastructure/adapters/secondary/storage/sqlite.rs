@@ -4,6 +4,8 @@ use anyhow::Result;
 use once_cell::sync::OnceCell;
 use sqlx::SqlitePool;
 
+use super::migrations::{migrate_sqlite, AppliedMigration};
+
 #[derive(Debug)]
 pub struct SqliteConnector {
     pub pool: SqlitePool,
@@ -14,15 +16,37 @@ static INSTANCE: OnceCell<Arc<SqliteConnector>> = OnceCell::new();
 impl SqliteConnector {
     pub async fn new(pool: Result<SqlitePool>) -> Result<Arc<Self>> {
         match INSTANCE.get() {
-            Some(x) => return Ok(x.clone()),
+            Some(x) => Ok(x.clone()),
             None => match pool {
                 Ok(x) => {
                     let ret = Arc::new(Self { pool: x });
                     INSTANCE.set(ret.clone()).expect("failed to set singleton");
-                    return Ok(ret);
+                    Ok(ret)
                 }
-                Err(e) => return Err(e.into()),
+                Err(e) => Err(e),
             },
         }
     }
+
+    /// Brings the schema up to date with the migrations embedded in the binary
+    /// and returns the ones it applied.
+    pub async fn migrate(&self) -> Result<Vec<AppliedMigration>> {
+        migrate_sqlite(&self.pool).await
+    }
+}
+
+/// Whether `error` means another writer got to the same rows first: a UNIQUE
+/// or PRIMARY KEY violation, or SQLITE_BUSY_SNAPSHOT when the snapshot this
+/// transaction read from went stale before it could write. Plain SQLITE_BUSY
+/// is lock contention, not a conflict, and is reported as it is.
+pub fn is_write_conflict(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::Database(e) => {
+            matches!(
+                e.code().as_deref(),
+                Some("517") | Some("1555") | Some("2067")
+            )
+        }
+        _ => false,
+    }
 }
@@ -20,9 +20,21 @@ where
     /// The type of aggregate instance
     #[sqlx(default)]
     pub aggregate_type: String,
-    /// The sequence id for an aggregate instance.
+    /// The unique id of the event.
     #[sqlx(default)]
     pub sequence: String,
+    /// The position of this event in its aggregate stream.
+    #[sqlx(default)]
+    pub version: i64,
+    /// The position of this event across all streams.
+    #[sqlx(default)]
+    pub position: Option<i64>,
+    /// The type of the stored event.
+    #[sqlx(default)]
+    pub event_type: String,
+    /// The version of the event's shape at the time it was stored.
+    #[sqlx(default)]
+    pub event_version: String,
     /// The event payload with all business information.
     #[sqlx(default)]
     pub payload: sqlx::types::Json<A>,
@@ -34,18 +46,20 @@ where
     pub timestamp: DateTime<Utc>,
 }
 
-impl<A: Default + Debug + Into<B::Event>, B: Aggregate> Into<EventEnvelope<B>>
-    for SQLEventEnvelope<A>
+impl<A: Default + Debug + Into<B::Event>, B: Aggregate> From<SQLEventEnvelope<A>>
+    for EventEnvelope<B>
 {
-    fn into(self) -> EventEnvelope<B> {
-        return EventEnvelope {
-            aggregate_id: self.aggregate_id,
-            aggregate_type: self.aggregate_type,
-            sequence: self.sequence,
-            payload: self.payload.0.into(),
-            metadata: self.metadata.0,
-            timestamp: self.timestamp,
-        };
+    fn from(val: SQLEventEnvelope<A>) -> Self {
+        EventEnvelope {
+            aggregate_id: val.aggregate_id,
+            aggregate_type: val.aggregate_type,
+            sequence: val.sequence,
+            version: val.version,
+            position: val.position,
+            payload: val.payload.0.into(),
+            metadata: val.metadata.0,
+            timestamp: val.timestamp,
+        }
     }
 }
 
@@ -63,9 +77,9 @@ where
     /// The event payload with all business information.
     #[sqlx(default)]
     pub payload: sqlx::types::Json<Q>,
-    /// The last committed event sequence ULID for this aggregate instance.
+    /// The stream version of the last event folded into this snapshot.
     #[sqlx(default)]
-    pub last_sequence: String,
+    pub version: i64,
     /// Additional metadata for use in auditing, logging or debugging purposes.
     #[sqlx(default)]
     pub snapshot_id: String,
@@ -74,17 +88,33 @@ where
     pub timestamp: DateTime<Utc>,
 }
 
-impl<Q: Default + Debug + Into<S>, S: Aggregate> Into<AggregateSnapshot<S>>
-    for SQLAggregateSnapshot<Q>
+impl<Q: Default + Debug + Into<S>, S: Aggregate> From<SQLAggregateSnapshot<Q>>
+    for AggregateSnapshot<S>
 {
-    fn into(self) -> AggregateSnapshot<S> {
-        return AggregateSnapshot {
-            aggregate_id: self.aggregate_id,
-            aggregate_type: self.aggregate_type,
-            payload: self.payload.0.into(),
-            last_sequence: self.last_sequence,
-            snapshot_id: self.snapshot_id,
-            timestamp: self.timestamp,
-        };
+    fn from(val: SQLAggregateSnapshot<Q>) -> Self {
+        AggregateSnapshot {
+            aggregate_id: val.aggregate_id,
+            aggregate_type: val.aggregate_type,
+            payload: val.payload.0.into(),
+            version: val.version,
+            snapshot_id: val.snapshot_id,
+            timestamp: val.timestamp,
+        }
     }
 }
+
+#[derive(FromRow, Debug)]
+pub struct SQLDeadLetter<A>
+where
+    A: Default,
+{
+    /// The event that could not be sent.
+    #[sqlx(flatten)]
+    pub event: SQLEventEnvelope<A>,
+    /// How many sends were attempted.
+    pub attempts: i64,
+    /// The error of the last attempt.
+    pub last_error: String,
+    /// When the entry left the outbox.
+    pub dead_lettered_at: DateTime<Utc>,
+}
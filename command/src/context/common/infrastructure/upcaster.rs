@@ -0,0 +1,158 @@
+use std::collections::HashSet;
+
+use anyhow::anyhow;
+use serde_json::Value;
+
+use crate::context::common::domain::entity::error::EventStoreError;
+
+/// Rewrites the stored payload of one `(event_type, event_version)` pair into
+/// the shape of `to_version`.
+pub struct Upcaster {
+    pub event_type: String,
+    pub from_version: String,
+    pub to_version: String,
+    pub transform: fn(Value) -> Value,
+}
+
+/// The upcasters known for an event store.
+///
+/// Stored payloads are run through every matching upcaster in turn, so a
+/// `0.0.1` event is brought up to date by chaining `0.0.1 -> 0.0.2` and
+/// `0.0.2 -> 0.0.3` rather than needing a dedicated `0.0.1 -> 0.0.3` step.
+#[derive(Default)]
+pub struct UpcasterRegistry {
+    upcasters: Vec<Upcaster>,
+}
+
+impl UpcasterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(
+        mut self,
+        event_type: &str,
+        from_version: &str,
+        to_version: &str,
+        transform: fn(Value) -> Value,
+    ) -> Self {
+        self.upcasters.push(Upcaster {
+            event_type: event_type.into(),
+            from_version: from_version.into(),
+            to_version: to_version.into(),
+            transform,
+        });
+        self
+    }
+
+    /// Upcasts `payload` as far as the registered upcasters allow and returns it
+    /// together with the event version it now has. Fails when the upcasters
+    /// of `event_type` form a cycle.
+    pub fn upcast(
+        &self,
+        event_type: &str,
+        event_version: &str,
+        payload: Value,
+    ) -> Result<(String, Value), EventStoreError> {
+        let mut version = event_version.to_string();
+        let mut payload = payload;
+        // A chain that needs an upcaster it has already applied would go round
+        // forever, so it is rejected instead.
+        let mut applied = HashSet::new();
+        while let Some((i, x)) = self
+            .upcasters
+            .iter()
+            .enumerate()
+            .find(|(_, x)| x.event_type == event_type && x.from_version == version)
+        {
+            if !applied.insert(i) {
+                return Err(EventStoreError::EventDecode {
+                    event_type: event_type.to_string(),
+                    event_version: event_version.to_string(),
+                    source: anyhow!("upcasters cycle back to version `{}`", version),
+                });
+            }
+            payload = (x.transform)(payload);
+            version = x.to_version.clone();
+        }
+        Ok((version, payload))
+    }
+}
+
+#[cfg(test)]
+mod upcaster_test {
+    use serde_json::{json, Value};
+
+    use crate::context::common::domain::entity::error::EventStoreError;
+
+    use super::UpcasterRegistry;
+
+    fn split_address(mut payload: Value) -> Value {
+        let address = payload["address"].take();
+        payload["address"] = json!({ "line1": address });
+        payload
+    }
+
+    fn add_country(mut payload: Value) -> Value {
+        payload["address"]["country"] = json!("US");
+        payload
+    }
+
+    #[test]
+    fn upcast_chains_registered_upcasters_in_version_order() {
+        let registry = UpcasterRegistry::new()
+            .register("PrescriptionUpdated", "0.0.2", "0.0.3", add_country)
+            .register("PrescriptionUpdated", "0.0.1", "0.0.2", split_address);
+
+        let (version, payload) = registry
+            .upcast(
+                "PrescriptionUpdated",
+                "0.0.1",
+                json!({ "event_type": "PrescriptionUpdated", "address": "1 Main St" }),
+            )
+            .unwrap();
+
+        assert_eq!(version, "0.0.3");
+        assert_eq!(
+            payload["address"],
+            json!({ "line1": "1 Main St", "country": "US" })
+        );
+    }
+
+    #[test]
+    fn upcast_leaves_current_and_unknown_events_untouched() {
+        let registry = UpcasterRegistry::new().register(
+            "PrescriptionUpdated",
+            "0.0.1",
+            "0.0.2",
+            split_address,
+        );
+        let payload = json!({ "address": "1 Main St" });
+
+        let current = registry.upcast("PrescriptionUpdated", "0.0.2", payload.clone());
+        let unknown = registry.upcast("PrescriptionCreated", "0.0.1", payload.clone());
+
+        assert_eq!(current.unwrap(), ("0.0.2".to_string(), payload.clone()));
+        assert_eq!(unknown.unwrap(), ("0.0.1".to_string(), payload));
+    }
+
+    #[test]
+    fn upcast_rejects_upcasters_that_cycle() {
+        let registry = UpcasterRegistry::new()
+            .register("PrescriptionUpdated", "0.0.1", "0.0.2", split_address)
+            .register("PrescriptionUpdated", "0.0.2", "0.0.1", add_country);
+
+        let result = registry.upcast(
+            "PrescriptionUpdated",
+            "0.0.1",
+            json!({ "address": "1 Main St" }),
+        );
+
+        match result {
+            Err(EventStoreError::EventDecode { event_version, .. }) => {
+                assert_eq!(event_version, "0.0.1")
+            }
+            x => panic!("unexpected result {:?}", x),
+        }
+    }
+}
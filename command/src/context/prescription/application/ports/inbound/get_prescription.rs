@@ -0,0 +1,24 @@
+use crate::context::prescription::domain::entity::view::PrescriptionView;
+use async_trait::async_trait;
+
+/// Narrows a prescription listing down to the given patient and/or medication.
+#[derive(Clone, Debug, Default)]
+pub struct PrescriptionFilter {
+    pub patient_id: Option<String>,
+    pub medication_id: Option<String>,
+}
+
+#[async_trait]
+pub trait GetPrescriptionUseCase<O>
+where
+    O: From<PrescriptionView>,
+{
+    async fn get_prescription(&self, id: &str) -> Result<Option<O>, anyhow::Error>;
+
+    async fn list_prescriptions(
+        &self,
+        filter: PrescriptionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<O>, anyhow::Error>;
+}
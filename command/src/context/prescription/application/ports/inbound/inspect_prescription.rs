@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+
+use crate::context::common::domain::entity::event::{AggregateSnapshot, EventEnvelope};
+use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+
+/// Everything stored about one prescription, for troubleshooting.
+pub struct PrescriptionInspection {
+    pub events: Vec<EventEnvelope<PrescriptionAggregate>>,
+    pub snapshot: Option<AggregateSnapshot<PrescriptionAggregate>>,
+    pub state: PrescriptionAggregate,
+    pub version: i64,
+}
+
+#[async_trait]
+pub trait InspectPrescriptionUseCase {
+    async fn inspect_prescription(&self, id: &str)
+        -> Result<PrescriptionInspection, anyhow::Error>;
+}
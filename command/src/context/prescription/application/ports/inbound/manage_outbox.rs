@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+
+use crate::context::common::domain::entity::event::{DeadLetter, EventEnvelope};
+use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+
+/// Outcome of one pass of the outbox relay.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RelayReport {
+    pub claimed: usize,
+    pub sent: usize,
+    // Sent, but the lease had moved to another worker, which sends the event
+    // again
+    pub lease_lost: usize,
+    // Sent, but still in the outbox as removing it failed, so it is sent again
+    pub not_deleted: usize,
+    pub failed: usize,
+    // Failed for the last allowed time and moved to the dead letters
+    pub dead_lettered: usize,
+    // Not sent in this pass as they queue behind an event of the same
+    // aggregate that failed or was not removed
+    pub skipped: usize,
+}
+
+#[async_trait]
+pub trait ManageOutboxUseCase {
+    // Pending events, oldest first
+    async fn list_outbox(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope<PrescriptionAggregate>>, anyhow::Error>;
+
+    // Claims the next batch of pending events and sends it to the bus
+    async fn relay_outbox(&self) -> Result<RelayReport, anyhow::Error>;
+
+    // Drops the given pending events, or all of them, without sending them
+    async fn purge_outbox(&self, event_ids: Option<Vec<String>>) -> Result<u64, anyhow::Error>;
+
+    // Events given up on after their last allowed attempt, oldest first
+    async fn list_dead_letters(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter<EventEnvelope<PrescriptionAggregate>>>, anyhow::Error>;
+
+    // Puts the given dead letters, or all of them, back into the outbox
+    async fn requeue_dead_letters(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error>;
+}
@@ -0,0 +1,8 @@
+use async_trait::async_trait;
+
+#[async_trait]
+pub trait ManageSnapshotsUseCase {
+    async fn prune_snapshots(&self) -> Result<u64, anyhow::Error>;
+
+    async fn regenerate_snapshots(&self) -> Result<usize, anyhow::Error>;
+}
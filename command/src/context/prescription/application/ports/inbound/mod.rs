@@ -1,4 +1,9 @@
 pub mod create_prescription;
 pub mod get_events;
+pub mod get_prescription;
+pub mod inspect_prescription;
+pub mod manage_outbox;
+pub mod manage_snapshots;
+pub mod rebuild_projection;
 pub mod send_event;
 pub mod update_prescription;
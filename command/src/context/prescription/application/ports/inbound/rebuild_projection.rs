@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+
+use crate::context::common::application::service::rebuild::RebuildProgress;
+
+#[async_trait]
+pub trait RebuildProjectionUseCase {
+    // Names of the projections that can be rebuilt
+    fn projections(&self) -> Vec<String>;
+
+    async fn rebuild_projection(&self, projection: &str) -> Result<RebuildProgress, anyhow::Error>;
+}
@@ -1,4 +1,4 @@
-use crate::context::common::domain::entity::event::EventEnvelope;
+use crate::context::common::domain::entity::event::{EventEnvelope, OutboxDelivery};
 use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
 use async_trait::async_trait;
 
@@ -7,5 +7,7 @@ pub trait SendEvent<I>
 where
     I: Into<EventEnvelope<PrescriptionAggregate>>,
 {
-    async fn send_event(&self, event: I) -> Result<(), anyhow::Error>;
+    // Fails when the bus did not take the event; otherwise tells whether it
+    // also left the outbox or is to be sent again
+    async fn send_event(&self, event: I) -> Result<OutboxDelivery, anyhow::Error>;
 }
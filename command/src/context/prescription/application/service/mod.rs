@@ -1,2 +1,5 @@
 pub mod outbox;
 pub mod prescription;
+pub mod projection;
+pub mod query;
+pub mod rebuild;
@@ -4,14 +4,28 @@ use crate::context::{
     common::application::ports::outbound::{
         event_bus::EventBus, event_repository::EventRepository,
     },
+    common::application::service::retry::RetryPolicy,
     common::domain::entity::event::AggregateSnapshot,
-    common::domain::entity::event::EventEnvelope,
-    prescription::application::ports::inbound::{get_events::GetEvents, send_event::SendEvent},
+    common::domain::entity::event::{DeadLetter, EventEnvelope, OutboxDelivery, MESSAGE_ID},
+    prescription::application::ports::inbound::{
+        get_events::GetEvents,
+        manage_outbox::{ManageOutboxUseCase, RelayReport},
+        send_event::SendEvent,
+    },
     prescription::domain::entity::aggregate::PrescriptionAggregate,
 };
 
 use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use ulid::Ulid;
+
+/// How many outbox entries `get_events` claims unless told otherwise.
+const DEFAULT_BATCH_SIZE: i64 = 100;
+/// How long a claimed batch stays leased unless told otherwise.
+const DEFAULT_LEASE_SECS: i64 = 30;
 
+/// Relays outbox entries to the bus. Each instance leases the entries it
+/// claims under an owner id of its own, so several relays can share an outbox.
 pub struct PrescriptionOutboxService {
     repository: Arc<
         dyn EventRepository<
@@ -27,6 +41,10 @@ pub struct PrescriptionOutboxService {
             + Sync
             + Send,
     >,
+    batch_size: i64,
+    owner: String,
+    lease: Duration,
+    retries: RetryPolicy,
 }
 
 impl PrescriptionOutboxService {
@@ -46,14 +64,66 @@ impl PrescriptionOutboxService {
                 + Send,
         >,
     ) -> Self {
-        return Self { repository, bus };
+        Self {
+            repository,
+            bus,
+            batch_size: DEFAULT_BATCH_SIZE,
+            owner: Ulid::new().to_string(),
+            lease: Duration::seconds(DEFAULT_LEASE_SECS),
+            retries: RetryPolicy::default(),
+        }
+    }
+
+    pub fn with_batch_size(mut self, batch_size: i64) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    pub fn with_lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, retries: RetryPolicy) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Schedules the next attempt of an event the bus failed to take, or
+    /// dead-letters it once its attempts are used up. Returns whether the
+    /// event was dead-lettered.
+    async fn record_failure(
+        &self,
+        event_id: &str,
+        error: &anyhow::Error,
+    ) -> Result<bool, anyhow::Error> {
+        let attempts = self
+            .repository
+            .record_outbox_failure(&self.owner, event_id, &format!("{:#}", error))
+            .await?;
+        match self.retries.retry_at(attempts, Utc::now()) {
+            Some(at) => {
+                self.repository
+                    .defer_outbox_event(&self.owner, event_id, at)
+                    .await?;
+                Ok(false)
+            }
+            None => {
+                self.repository
+                    .dead_letter_outbox_event(&self.owner, event_id)
+                    .await?;
+                Ok(true)
+            }
+        }
     }
 }
 
 #[async_trait]
 impl GetEvents<EventEnvelope<PrescriptionAggregate>> for PrescriptionOutboxService {
     async fn get_events(&self) -> Result<Vec<EventEnvelope<PrescriptionAggregate>>, anyhow::Error> {
-        self.repository.retrieve_outbox_events().await
+        self.repository
+            .claim_outbox_events(&self.owner, self.batch_size, self.lease)
+            .await
     }
 }
 
@@ -61,10 +131,218 @@ impl GetEvents<EventEnvelope<PrescriptionAggregate>> for PrescriptionOutboxServi
 impl SendEvent<EventEnvelope<PrescriptionAggregate>> for PrescriptionOutboxService {
     async fn send_event(
         &self,
-        event: EventEnvelope<PrescriptionAggregate>,
-    ) -> Result<(), anyhow::Error> {
+        mut event: EventEnvelope<PrescriptionAggregate>,
+    ) -> Result<OutboxDelivery, anyhow::Error> {
+        event
+            .metadata
+            .insert(MESSAGE_ID.to_string(), event.message_id());
         self.repository
-            .send_and_delete_outbox_event(event, &self.bus)
+            .send_and_delete_outbox_event(&self.owner, event, &self.bus)
             .await
     }
 }
+
+#[async_trait]
+impl ManageOutboxUseCase for PrescriptionOutboxService {
+    async fn list_outbox(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<EventEnvelope<PrescriptionAggregate>>, anyhow::Error> {
+        self.repository.retrieve_outbox_events(limit).await
+    }
+
+    async fn relay_outbox(&self) -> Result<RelayReport, anyhow::Error> {
+        let events = self.get_events().await?;
+        let mut report = RelayReport {
+            claimed: events.len(),
+            ..RelayReport::default()
+        };
+        // Later events of an aggregate wait until its failed or unremoved one
+        // is sent again, so that they do not overtake it.
+        let mut held: Vec<String> = vec![];
+        for event in events {
+            if held.contains(&event.aggregate_id) {
+                report.skipped += 1;
+                continue;
+            }
+            let aggregate_id = event.aggregate_id.clone();
+            let event_id = event.sequence.clone();
+            match self.send_event(event).await {
+                Ok(OutboxDelivery::Sent) => report.sent += 1,
+                Ok(OutboxDelivery::LeaseLost) => {
+                    report.lease_lost += 1;
+                    held.push(aggregate_id);
+                }
+                Ok(OutboxDelivery::NotDeleted(e)) => {
+                    // The bus has the event, so this is no failed send to
+                    // retry or dead-letter.
+                    println!("Sent event {} but failed to remove it: {:?}", event_id, e);
+                    report.not_deleted += 1;
+                    held.push(aggregate_id);
+                }
+                Err(e) => {
+                    println!("Received error sending event: {:?}", e);
+                    report.failed += 1;
+                    held.push(aggregate_id);
+                    match self.record_failure(&event_id, &e).await {
+                        Ok(true) => report.dead_lettered += 1,
+                        Ok(false) => {}
+                        Err(e) => println!("Failed to record send failure: {:?}", e),
+                    }
+                }
+            }
+        }
+        // Whatever is left unsent is up for grabs again right away.
+        self.repository.release_outbox_events(&self.owner).await?;
+        Ok(report)
+    }
+
+    async fn purge_outbox(&self, event_ids: Option<Vec<String>>) -> Result<u64, anyhow::Error> {
+        self.repository.delete_outbox_events(event_ids).await
+    }
+
+    async fn list_dead_letters(
+        &self,
+        limit: i64,
+    ) -> Result<Vec<DeadLetter<EventEnvelope<PrescriptionAggregate>>>, anyhow::Error> {
+        self.repository.retrieve_dead_letters(limit).await
+    }
+
+    async fn requeue_dead_letters(
+        &self,
+        event_ids: Option<Vec<String>>,
+    ) -> Result<u64, anyhow::Error> {
+        self.repository.requeue_dead_letters(event_ids).await
+    }
+}
+
+#[cfg(test)]
+mod outbox_test {
+    use std::pin::Pin;
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use async_trait::async_trait;
+    use chrono::Duration;
+    use futures::Stream;
+
+    use crate::context::common::application::ports::outbound::event_bus::EventBus;
+    use crate::context::common::application::service::retry::RetryPolicy;
+    use crate::context::common::domain::entity::event::EventEnvelope;
+    use crate::context::common::domain::entity::filter::EventFilter;
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::InMemoryEventRepository;
+    use crate::context::prescription::application::ports::inbound::create_prescription::CreatePrescriptionUseCase;
+    use crate::context::prescription::application::ports::inbound::manage_outbox::ManageOutboxUseCase;
+    use crate::context::prescription::application::ports::inbound::update_prescription::UpdatePrescriptionUseCase;
+    use crate::context::prescription::application::ports::outbound::prescription::MockPrescriptionServices;
+    use crate::context::prescription::application::service::prescription::PrescriptionService;
+    use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+    use crate::context::prescription::domain::entity::command::{
+        CreatePrescriptionCommand, UpdatePrescriptionCommand,
+    };
+    use crate::context::prescription::infrastructure::dtos::transport::http::RESTPrescriptionQuery;
+
+    use super::PrescriptionOutboxService;
+
+    struct BrokenBus;
+
+    #[async_trait]
+    impl EventBus<EventEnvelope<PrescriptionAggregate>, EventEnvelope<PrescriptionAggregate>>
+        for BrokenBus
+    {
+        async fn send_event(
+            &self,
+            _event: EventEnvelope<PrescriptionAggregate>,
+        ) -> Result<(), anyhow::Error> {
+            Err(anyhow!("bus unavailable"))
+        }
+
+        async fn receive_events(
+            &self,
+        ) -> Pin<Box<dyn Stream<Item = EventEnvelope<PrescriptionAggregate>> + Send>> {
+            Box::pin(futures::stream::empty())
+        }
+
+        async fn receive_group(
+            &self,
+            _group: &str,
+        ) -> Pin<Box<dyn Stream<Item = EventEnvelope<PrescriptionAggregate>> + Send>> {
+            Box::pin(futures::stream::empty())
+        }
+
+        async fn receive_filtered(
+            &self,
+            _filter: EventFilter,
+            _group: Option<&str>,
+        ) -> Pin<Box<dyn Stream<Item = EventEnvelope<PrescriptionAggregate>> + Send>> {
+            Box::pin(futures::stream::empty())
+        }
+    }
+
+    #[tokio::test]
+    async fn failed_sends_back_off_then_dead_letter_until_requeued() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        let service = PrescriptionService::new(
+            Box::new(MockPrescriptionServices::new()),
+            repository.clone(),
+        );
+        let created: RESTPrescriptionQuery = service
+            .create_prescription(
+                CreatePrescriptionCommand {
+                    medication_id: "1234".into(),
+                    patient_id: "5678".into(),
+                    address: "old".into(),
+                },
+                vec![],
+            )
+            .await
+            .unwrap();
+        let _: RESTPrescriptionQuery = service
+            .update_prescription(
+                UpdatePrescriptionCommand {
+                    id: created.id.unwrap(),
+                    address: "new".into(),
+                },
+                vec![],
+            )
+            .await
+            .unwrap();
+        let relay = |base_delay| {
+            PrescriptionOutboxService::new(repository.clone(), Arc::new(BrokenBus))
+                .with_retry_policy(RetryPolicy {
+                    max_attempts: 2,
+                    base_delay,
+                    max_delay: Duration::minutes(1),
+                })
+        };
+
+        let outbox = relay(Duration::zero());
+        let first = outbox.relay_outbox().await.unwrap();
+        let second = outbox.relay_outbox().await.unwrap();
+
+        assert_eq!(
+            (first.failed, first.skipped, first.dead_lettered),
+            (1, 1, 0)
+        );
+        assert_eq!(
+            (second.failed, second.skipped, second.dead_lettered),
+            (1, 1, 1)
+        );
+        let dead_letters = outbox.list_dead_letters(10).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].event.version, 1);
+        assert_eq!(dead_letters[0].attempts, 2);
+        assert_eq!(dead_letters[0].last_error, "bus unavailable");
+
+        assert_eq!(outbox.requeue_dead_letters(None).await.unwrap(), 1);
+        let pending = outbox.list_outbox(10).await.unwrap();
+        assert_eq!(
+            pending.iter().map(|x| x.version).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        let outbox = relay(Duration::minutes(1));
+        assert_eq!(outbox.relay_outbox().await.unwrap().failed, 1);
+        assert_eq!(outbox.relay_outbox().await.unwrap().claimed, 0);
+    }
+}
@@ -2,56 +2,67 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use chrono::Utc;
 
-use crate::context::common::application::ports::outbound::event_repository::EventRepository;
-use crate::context::common::domain::entity::aggregate::{self, Aggregate};
-use crate::context::common::domain::entity::event::DomainEvent;
-use crate::context::common::domain::entity::event::{AggregateSnapshot, EventEnvelope};
+use crate::context::common::application::ports::outbound::event_repository::AggregateRepository;
+use crate::context::common::application::ports::outbound::outbox_signal::OutboxSignal;
+use crate::context::common::application::service::command::CommandExecutor;
+use crate::context::common::application::service::snapshot::{SnapshotPolicy, SnapshotRetention};
 use crate::context::prescription::application::ports::inbound::create_prescription::CreatePrescriptionUseCase;
+use crate::context::prescription::application::ports::inbound::inspect_prescription::{
+    InspectPrescriptionUseCase, PrescriptionInspection,
+};
+use crate::context::prescription::application::ports::inbound::manage_snapshots::ManageSnapshotsUseCase;
 use crate::context::prescription::application::ports::inbound::update_prescription::UpdatePrescriptionUseCase;
 use crate::context::prescription::application::ports::outbound::prescription::PrescriptionServices;
 use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
 use crate::context::prescription::domain::entity::command::{
-    CreatePrescriptionCommand, PrescriptionCommand, UpdatePrescriptionCommand,
+    CreatePrescriptionCommand, UpdatePrescriptionCommand,
 };
-use crate::context::prescription::domain::entity::error::PrescriptionError;
+
+/// How often a command is re-run against a reloaded aggregate after losing an
+/// optimistic concurrency race.
+const MAX_RETRIES: usize = 3;
 
 pub trait ServiceTrait<O: From<PrescriptionAggregate>>:
-    CreatePrescriptionUseCase<O> + UpdatePrescriptionUseCase<O>
+    CreatePrescriptionUseCase<O> + UpdatePrescriptionUseCase<O> + ManageSnapshotsUseCase
 {
 }
 
 pub struct PrescriptionService {
-    services: Box<dyn Sync + Send + PrescriptionServices>,
-    repository: Arc<
-        dyn EventRepository<
-                EventEnvelope<PrescriptionAggregate>,
-                EventEnvelope<PrescriptionAggregate>,
-                AggregateSnapshot<PrescriptionAggregate>,
-                AggregateSnapshot<PrescriptionAggregate>,
-            > + Sync
-            + Send,
-    >,
+    executor: CommandExecutor<PrescriptionAggregate, AggregateRepository<PrescriptionAggregate>>,
+    repository: Arc<AggregateRepository<PrescriptionAggregate>>,
 }
 
 impl PrescriptionService {
     pub fn new(
         services: Box<dyn Sync + Send + PrescriptionServices>,
-        repository: Arc<
-            dyn EventRepository<
-                    EventEnvelope<PrescriptionAggregate>,
-                    EventEnvelope<PrescriptionAggregate>,
-                    AggregateSnapshot<PrescriptionAggregate>,
-                    AggregateSnapshot<PrescriptionAggregate>,
-                > + Sync
-                + Send,
-        >,
+        repository: Arc<AggregateRepository<PrescriptionAggregate>>,
     ) -> Self {
-        return Self {
-            services,
+        Self {
+            executor: CommandExecutor::new(repository.clone(), services).with_retries(MAX_RETRIES),
             repository,
-        };
+        }
+    }
+
+    pub fn with_snapshot_policy(self, policy: SnapshotPolicy) -> Self {
+        Self {
+            executor: self.executor.with_snapshot_policy(policy),
+            ..self
+        }
+    }
+
+    pub fn with_snapshot_retention(self, retention: SnapshotRetention) -> Self {
+        Self {
+            executor: self.executor.with_snapshot_retention(retention),
+            ..self
+        }
+    }
+
+    pub fn with_outbox_signal(self, signal: Arc<dyn OutboxSignal + Send + Sync>) -> Self {
+        Self {
+            executor: self.executor.with_outbox_signal(signal),
+            ..self
+        }
     }
 }
 
@@ -65,44 +76,11 @@ where
         prescription: CreatePrescriptionCommand,
         _fields: Vec<&str>,
     ) -> Result<O, anyhow::Error> {
-        let mut aggregate = PrescriptionAggregate::default();
-        let command: PrescriptionCommand = prescription.into();
-        let events = aggregate.handle(command, &self.services).await;
-        match events {
-            Ok(v) => {
-                for event in &v {
-                    aggregate.apply(event.clone());
-                }
-                if aggregate.aggregate_id().is_none() {
-                    return Err(PrescriptionError::UnknownError.into());
-                }
-                let wrapped_events: Vec<EventEnvelope<PrescriptionAggregate>> = v
-                    .iter()
-                    .map(|x| EventEnvelope::<PrescriptionAggregate> {
-                        aggregate_id: aggregate.aggregate_id().unwrap(),
-                        aggregate_type: "prescription".into(),
-                        sequence: x.event_id(),
-                        payload: x.clone(),
-                        metadata: HashMap::new(),
-                        timestamp: Utc::now(),
-                    })
-                    .collect();
-                let result = self.repository.store_events(wrapped_events).await;
-                match result {
-                    Err(_) => return Err(PrescriptionError::UnknownError.into()),
-                    _ => {}
-                }
-                match aggregate.snapshot() {
-                    Some(x) => match self.repository.store_snapshot(x).await {
-                        Err(_e) => println!("Failed to persist snapshot"),
-                        _ => {}
-                    },
-                    None => {}
-                }
-                return Ok(aggregate.into());
-            }
-            Err(e) => return Err(e.into()),
-        }
+        let aggregate = self
+            .executor
+            .execute(None, prescription.into(), HashMap::new())
+            .await?;
+        Ok(aggregate.into())
     }
 }
 
@@ -116,65 +94,99 @@ where
         prescription: UpdatePrescriptionCommand,
         _fields: Vec<&str>,
     ) -> Result<O, anyhow::Error> {
-        let mut aggregate = PrescriptionAggregate::default();
+        let aggregate = self
+            .executor
+            .execute(
+                Some(prescription.id.clone()),
+                prescription.into(),
+                HashMap::new(),
+            )
+            .await?;
+        Ok(aggregate.into())
+    }
+}
+
+#[async_trait]
+impl ManageSnapshotsUseCase for PrescriptionService {
+    async fn prune_snapshots(&self) -> Result<u64, anyhow::Error> {
+        self.executor.prune_snapshots().await
+    }
+
+    async fn regenerate_snapshots(&self) -> Result<usize, anyhow::Error> {
+        self.executor.regenerate_snapshots().await
+    }
+}
+
+#[async_trait]
+impl InspectPrescriptionUseCase for PrescriptionService {
+    async fn inspect_prescription(
+        &self,
+        id: &str,
+    ) -> Result<PrescriptionInspection, anyhow::Error> {
+        let events = self
+            .repository
+            .retrieve_events(id.to_string(), None)
+            .await?;
         let snapshot = self
             .repository
-            .retrieve_latest_snapshot(prescription.id.clone())
+            .retrieve_latest_snapshot(id.to_string())
             .await?;
-        let past_events = match snapshot {
-            Some(x) => {
-                aggregate = x.payload;
-                self.repository
-                    .retrieve_events(prescription.id.clone(), Some(x.last_sequence))
-                    .await?
-            }
-            None => {
-                self.repository
-                    .retrieve_events(prescription.id.clone(), None)
-                    .await?
-            }
-        };
-        for event in past_events.iter() {
-            aggregate.apply(event.payload.clone());
-        }
-        let command: PrescriptionCommand = prescription.into();
-        let events = aggregate.handle(command, &self.services).await;
-        match events {
-            Ok(v) => {
-                for event in &v {
-                    aggregate.apply(event.clone());
-                }
-                if aggregate.aggregate_id().is_none() {
-                    return Err(PrescriptionError::UnknownError.into());
-                }
-                let wrapped_events: Vec<EventEnvelope<PrescriptionAggregate>> = v
-                    .iter()
-                    .map(|x| EventEnvelope::<PrescriptionAggregate> {
-                        aggregate_id: aggregate.aggregate_id().unwrap(),
-                        aggregate_type: "prescription".into(),
-                        sequence: x.event_id(),
-                        payload: x.clone(),
-                        metadata: HashMap::new(),
-                        timestamp: Utc::now(),
-                    })
-                    .collect();
-                let result = self.repository.store_events(wrapped_events).await;
-                match result {
-                    Err(_) => return Err(PrescriptionError::UnknownError.into()),
-                    _ => {}
-                }
-                match aggregate.snapshot() {
-                    Some(x) => match self.repository.store_snapshot(x).await {
-                        Err(_e) => println!("Failed to persist snapshot"),
-                        _ => {}
-                    },
-                    None => {}
-                }
-                return Ok(aggregate.into());
-            }
-            Err(e) => return Err(e.into()),
-        }
+        let (state, version) = self.executor.load(id).await?;
+        Ok(PrescriptionInspection {
+            events,
+            snapshot,
+            state,
+            version,
+        })
     }
 }
 
 impl<O: From<PrescriptionAggregate>> ServiceTrait<O> for PrescriptionService {}
+
+#[cfg(test)]
+mod prescription_service_test {
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::InMemoryEventRepository;
+    use crate::context::prescription::application::ports::inbound::create_prescription::CreatePrescriptionUseCase;
+    use crate::context::prescription::application::ports::inbound::update_prescription::UpdatePrescriptionUseCase;
+    use crate::context::prescription::application::ports::outbound::prescription::MockPrescriptionServices;
+    use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+    use crate::context::prescription::domain::entity::command::{
+        CreatePrescriptionCommand, UpdatePrescriptionCommand,
+    };
+    use crate::context::prescription::infrastructure::dtos::transport::http::RESTPrescriptionQuery;
+
+    use super::PrescriptionService;
+
+    #[tokio::test]
+    async fn update_prescription_rehydrates_aggregate_created_earlier() {
+        let repository = InMemoryEventRepository::<PrescriptionAggregate>::new();
+        let service =
+            PrescriptionService::new(Box::new(MockPrescriptionServices::new()), repository);
+
+        let created: RESTPrescriptionQuery = service
+            .create_prescription(
+                CreatePrescriptionCommand {
+                    medication_id: "1234".into(),
+                    patient_id: "5678".into(),
+                    address: "old".into(),
+                },
+                vec![],
+            )
+            .await
+            .unwrap();
+        let updated: RESTPrescriptionQuery = service
+            .update_prescription(
+                UpdatePrescriptionCommand {
+                    id: created.id.clone().unwrap(),
+                    address: "new".into(),
+                },
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(updated.id, created.id);
+        assert_eq!(updated.patient_id, Some("5678".into()));
+        assert_eq!(updated.address, Some("new".into()));
+    }
+}
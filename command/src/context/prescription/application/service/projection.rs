@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::common::application::ports::outbound::read_model::ReadModelRepository;
+use crate::context::common::application::service::projection::Projection;
+use crate::context::common::domain::entity::event::EventEnvelope;
+use crate::context::prescription::domain::entity::{
+    aggregate::PrescriptionAggregate, event::PrescriptionEvent, view::PrescriptionView,
+};
+
+pub const PRESCRIPTION_VIEW_PROJECTION: &str = "prescription_view";
+
+/// Maintains one `PrescriptionView` per prescription.
+pub struct PrescriptionViewProjection {
+    views: Arc<dyn ReadModelRepository<PrescriptionView> + Sync + Send>,
+}
+
+impl PrescriptionViewProjection {
+    pub fn new(views: Arc<dyn ReadModelRepository<PrescriptionView> + Sync + Send>) -> Self {
+        Self { views }
+    }
+}
+
+#[async_trait]
+impl Projection<PrescriptionAggregate> for PrescriptionViewProjection {
+    fn name(&self) -> String {
+        PRESCRIPTION_VIEW_PROJECTION.to_string()
+    }
+
+    async fn handle(
+        &self,
+        event: &EventEnvelope<PrescriptionAggregate>,
+    ) -> Result<(), anyhow::Error> {
+        let view = match &event.payload {
+            PrescriptionEvent::PrescriptionCreated {
+                id,
+                patient_id,
+                medication_id,
+                address,
+                ..
+            } => PrescriptionView {
+                id: id.clone(),
+                patient_id: patient_id.clone(),
+                medication_id: medication_id.clone(),
+                address: address.clone(),
+            },
+            PrescriptionEvent::PrescriptionUpdated { address, .. } => {
+                match self.views.load(&event.aggregate_id).await? {
+                    Some(x) => PrescriptionView {
+                        address: address.clone(),
+                        ..x
+                    },
+                    None => return Ok(()),
+                }
+            }
+        };
+        self.views
+            .save(&event.aggregate_id, event.version, view)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod projection_test {
+    use crate::context::common::application::ports::outbound::read_model::ReadModelRepository;
+    use crate::context::common::application::service::projection::Projection;
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::InMemoryReadModelStore;
+    use crate::context::prescription::domain::entity::{
+        fixture::{created, updated},
+        view::PrescriptionView,
+    };
+
+    use super::PrescriptionViewProjection;
+
+    #[tokio::test]
+    async fn projection_keeps_view_in_line_with_events() {
+        let views = InMemoryReadModelStore::<PrescriptionView>::new();
+        let projection = PrescriptionViewProjection::new(views.clone());
+
+        projection.handle(&created("1", "old")).await.unwrap();
+        projection.handle(&updated("1", 2, "new")).await.unwrap();
+
+        let view = views.load("1").await.unwrap().unwrap();
+        assert_eq!(view.patient_id, "p");
+        assert_eq!(view.address, "new");
+        let by_patient = views
+            .query(vec![("patient_id".into(), "p".into())], 10, 0)
+            .await
+            .unwrap();
+        let by_other = views
+            .query(vec![("patient_id".into(), "q".into())], 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(by_patient, vec![view]);
+        assert!(by_other.is_empty());
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::context::common::application::ports::outbound::read_model::ReadModelRepository;
+use crate::context::prescription::application::ports::inbound::get_prescription::{
+    GetPrescriptionUseCase, PrescriptionFilter,
+};
+use crate::context::prescription::domain::entity::view::PrescriptionView;
+
+pub struct PrescriptionQueryService {
+    views: Arc<dyn ReadModelRepository<PrescriptionView> + Sync + Send>,
+}
+
+impl PrescriptionQueryService {
+    pub fn new(views: Arc<dyn ReadModelRepository<PrescriptionView> + Sync + Send>) -> Self {
+        Self { views }
+    }
+}
+
+#[async_trait]
+impl<O> GetPrescriptionUseCase<O> for PrescriptionQueryService
+where
+    O: From<PrescriptionView>,
+{
+    async fn get_prescription(&self, id: &str) -> Result<Option<O>, anyhow::Error> {
+        Ok(self.views.load(id).await?.map(|x| x.into()))
+    }
+
+    async fn list_prescriptions(
+        &self,
+        filter: PrescriptionFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<O>, anyhow::Error> {
+        let mut fields = vec![];
+        if let Some(x) = filter.patient_id {
+            fields.push(("patient_id".to_string(), x));
+        }
+        if let Some(x) = filter.medication_id {
+            fields.push(("medication_id".to_string(), x));
+        }
+        let views = self.views.query(fields, limit, offset).await?;
+        Ok(views.into_iter().map(|x| x.into()).collect())
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::Mutex;
+
+use crate::context::common::application::ports::outbound::{
+    checkpoint_store::CheckpointStore, event_repository::AggregateRepository,
+    read_model::ReadModelRepository,
+};
+use crate::context::common::application::service::rebuild::{
+    ProjectionRebuild, RebuildLock, RebuildProgress,
+};
+use crate::context::common::domain::entity::error::ReadModelError;
+use crate::context::prescription::application::ports::inbound::rebuild_projection::RebuildProjectionUseCase;
+use crate::context::prescription::domain::entity::{
+    aggregate::PrescriptionAggregate, view::PrescriptionView,
+};
+
+use super::projection::{PrescriptionViewProjection, PRESCRIPTION_VIEW_PROJECTION};
+
+/// Rebuilds the read models of the prescription context by projection name.
+pub struct PrescriptionRebuildService {
+    view: ProjectionRebuild<PrescriptionAggregate, PrescriptionView>,
+    // Two rebuilds of the same read model would share its shadow table.
+    running: Mutex<()>,
+}
+
+impl PrescriptionRebuildService {
+    pub fn new(
+        repository: Arc<AggregateRepository<PrescriptionAggregate>>,
+        checkpoints: Arc<dyn CheckpointStore + Sync + Send>,
+        views: Arc<dyn ReadModelRepository<PrescriptionView> + Sync + Send>,
+    ) -> Self {
+        Self {
+            view: ProjectionRebuild::new(
+                repository,
+                checkpoints,
+                views,
+                Box::new(|x| Arc::new(PrescriptionViewProjection::new(x))),
+            ),
+            running: Mutex::new(()),
+        }
+    }
+
+    /// Shares `lock` with the `ProjectionRunner` keeping the views up to date.
+    pub fn with_rebuild_lock(mut self, lock: RebuildLock) -> Self {
+        self.view = self.view.with_rebuild_lock(lock);
+        self
+    }
+}
+
+#[async_trait]
+impl RebuildProjectionUseCase for PrescriptionRebuildService {
+    fn projections(&self) -> Vec<String> {
+        vec![PRESCRIPTION_VIEW_PROJECTION.to_string()]
+    }
+
+    async fn rebuild_projection(&self, projection: &str) -> Result<RebuildProgress, anyhow::Error> {
+        if projection != PRESCRIPTION_VIEW_PROJECTION {
+            return Err(ReadModelError::UnknownProjection(projection.to_string()).into());
+        }
+        let _running = self
+            .running
+            .try_lock()
+            .map_err(|_| ReadModelError::RebuildInProgress(projection.to_string()))?;
+        self.view
+            .run(|x| {
+                println!(
+                    "Rebuilding {}: {} events replayed, at position {}",
+                    projection, x.events, x.position
+                )
+            })
+            .await
+    }
+}
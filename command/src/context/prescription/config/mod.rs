@@ -0,0 +1,525 @@
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Deserialize;
+use toml::{value::Table, Value};
+
+use crate::context::common::application::service::retry::RetryPolicy;
+use crate::context::common::application::service::snapshot::{SnapshotPolicy, SnapshotRetention};
+use crate::context::common::domain::entity::error::ConfigError;
+
+/// The configuration file read when none is given explicitly; it may be absent.
+pub const DEFAULT_CONFIG_FILE: &str = "command.toml";
+
+/// Environment variables named `COMMAND_<SECTION>_<KEY>` override the file,
+/// e.g. `COMMAND_STORAGE_POOL_SIZE=10` sets `storage.pool_size`.
+const ENV_PREFIX: &str = "COMMAND_";
+
+/// The sections environment variables may set; other `COMMAND_` variables are
+/// left alone, as they may belong to something else.
+const ENV_SECTIONS: [&str; 6] = ["storage", "http", "outbox", "snapshot", "bus", "inbox"];
+
+/// Still honoured as `storage.url`, below `COMMAND_STORAGE_URL`.
+const LEGACY_DATABASE_URL: &str = "DATABASE_URL";
+
+/// Settings of the command service.
+///
+/// Each setting comes from the first of these that has it: `--set` flags, the
+/// environment, the TOML file, the defaults below. `load` validates the result
+/// so that a bad value stops the service at startup.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub storage: StorageSettings,
+    pub http: HttpSettings,
+    pub outbox: OutboxSettings,
+    pub snapshot: SnapshotSettings,
+    pub bus: BusSettings,
+    pub inbox: InboxSettings,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageBackend {
+    Sqlite,
+    Postgres,
+    Memory,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    /// Picked from the scheme of `url` when not set.
+    pub backend: Option<StorageBackend>,
+    pub url: String,
+    pub pool_size: u32,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self {
+            backend: None,
+            url: "sqlite://test.db?mode=rwc".to_string(),
+            pool_size: 5,
+        }
+    }
+}
+
+impl StorageSettings {
+    pub fn backend(&self) -> Result<StorageBackend, String> {
+        let scheme = self.url.split(':').next().unwrap_or_default();
+        let implied = match scheme {
+            "sqlite" => Some(StorageBackend::Sqlite),
+            "postgres" | "postgresql" => Some(StorageBackend::Postgres),
+            "memory" => Some(StorageBackend::Memory),
+            _ => None,
+        };
+        match (self.backend, implied) {
+            (Some(StorageBackend::Memory), _) => Ok(StorageBackend::Memory),
+            (Some(x), Some(y)) if x == y => Ok(x),
+            (Some(x), _) => Err(format!("storage.url `{}` is not a {:?} url", self.url, x)),
+            (None, Some(x)) => Ok(x),
+            (None, None) => Err(format!(
+                "storage.url `{}` has no known scheme, expected sqlite:, postgres:// or memory:",
+                self.url
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpSettings {
+    pub bind: String,
+    /// Where the unauthenticated admin endpoints listen; they are off unless
+    /// set, and should only be reachable by operators.
+    pub admin_bind: Option<String>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            bind: "0.0.0.0:3000".to_string(),
+            admin_bind: None,
+        }
+    }
+}
+
+impl HttpSettings {
+    pub fn bind(&self) -> Result<SocketAddr, String> {
+        self.bind
+            .parse()
+            .map_err(|_| format!("http.bind `{}` is not a socket address", self.bind))
+    }
+
+    pub fn admin_bind(&self) -> Result<Option<SocketAddr>, String> {
+        match &self.admin_bind {
+            Some(x) => x
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("http.admin_bind `{}` is not a socket address", x)),
+            None => Ok(None),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutboxSettings {
+    /// Seconds between two fallback sweeps of the outbox relay, which is
+    /// otherwise woken by every commit.
+    pub interval_secs: u64,
+    /// Most outbox entries relayed per run.
+    pub batch_size: i64,
+    /// Seconds a relay worker may hold its batch before other workers can
+    /// claim it, e.g. after a crash.
+    pub lease_secs: i64,
+    /// Sends attempted before an entry is moved to the dead letters.
+    pub max_attempts: i64,
+    /// Seconds before the first retry of a failed send; doubled per failure.
+    pub backoff_secs: i64,
+    /// Longest pause between two retries, in seconds.
+    pub max_backoff_secs: i64,
+}
+
+impl Default for OutboxSettings {
+    fn default() -> Self {
+        Self {
+            interval_secs: 10,
+            batch_size: 100,
+            lease_secs: 30,
+            max_attempts: 10,
+            backoff_secs: 1,
+            max_backoff_secs: 300,
+        }
+    }
+}
+
+impl OutboxSettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    pub fn lease(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.lease_secs)
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts,
+            base_delay: chrono::Duration::seconds(self.backoff_secs),
+            max_delay: chrono::Duration::seconds(self.max_backoff_secs),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotPolicyKind {
+    Never,
+    EveryNEvents,
+    Interval,
+    EventBytes,
+}
+
+/// Which `SnapshotPolicy` prescriptions use; only the threshold belonging to
+/// `policy` is looked at.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SnapshotSettings {
+    pub policy: SnapshotPolicyKind,
+    pub events: i64,
+    pub interval_secs: i64,
+    pub bytes: usize,
+    /// How many snapshots of each prescription survive pruning.
+    pub keep_last: i64,
+    /// When set, pruning keeps the snapshots younger than this many seconds
+    /// instead of the newest `keep_last`.
+    pub max_age_secs: Option<i64>,
+}
+
+impl Default for SnapshotSettings {
+    fn default() -> Self {
+        Self {
+            policy: SnapshotPolicyKind::EveryNEvents,
+            events: 10,
+            interval_secs: 3600,
+            bytes: 64 * 1024,
+            keep_last: 2,
+            max_age_secs: None,
+        }
+    }
+}
+
+impl SnapshotSettings {
+    pub fn policy(&self) -> SnapshotPolicy {
+        match self.policy {
+            SnapshotPolicyKind::Never => SnapshotPolicy::Never,
+            SnapshotPolicyKind::EveryNEvents => SnapshotPolicy::EveryNEvents(self.events),
+            SnapshotPolicyKind::Interval => {
+                SnapshotPolicy::Interval(chrono::Duration::seconds(self.interval_secs))
+            }
+            SnapshotPolicyKind::EventBytes => SnapshotPolicy::EventBytes(self.bytes),
+        }
+    }
+
+    pub fn retention(&self) -> SnapshotRetention {
+        match self.max_age_secs {
+            Some(x) => SnapshotRetention::MaxAge(chrono::Duration::seconds(x)),
+            None => SnapshotRetention::KeepLast(self.keep_last),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BusKind {
+    /// Still accepted as `channel`, the bus it replaced.
+    #[serde(alias = "channel")]
+    Broadcast,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BusSettings {
+    pub kind: BusKind,
+    /// Events a subscriber may fall behind before sends wait for it.
+    pub capacity: usize,
+}
+
+impl Default for BusSettings {
+    fn default() -> Self {
+        Self {
+            kind: BusKind::Broadcast,
+            capacity: 1024,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InboxSettings {
+    /// Seconds a processed message id is remembered; redeliveries arriving
+    /// later are processed again.
+    pub retention_secs: i64,
+}
+
+impl Default for InboxSettings {
+    fn default() -> Self {
+        Self {
+            retention_secs: 7 * 24 * 3600,
+        }
+    }
+}
+
+impl InboxSettings {
+    pub fn retention(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.retention_secs)
+    }
+}
+
+impl Settings {
+    /// Loads and validates the settings from `file`, or `DEFAULT_CONFIG_FILE`
+    /// if it exists, the process environment and `overrides` given as
+    /// `section.key=value`.
+    pub fn load(file: Option<&Path>, overrides: &[String]) -> Result<Self, ConfigError> {
+        let file = match file {
+            Some(x) => Some(x),
+            None => Some(Path::new(DEFAULT_CONFIG_FILE)).filter(|x| x.exists()),
+        };
+        let contents = match file {
+            Some(path) => {
+                let contents =
+                    std::fs::read_to_string(path).map_err(|source| ConfigError::Read {
+                        path: path.display().to_string(),
+                        source,
+                    })?;
+                Some((path.display().to_string(), contents))
+            }
+            None => None,
+        };
+        Self::from_layers(
+            contents.as_ref().map(|(x, y)| (x.as_str(), y.as_str())),
+            std::env::vars(),
+            overrides,
+        )
+    }
+
+    /// Builds the settings from the `(name, contents)` of a TOML file, the
+    /// environment variables `env` and `overrides`, then validates them.
+    pub fn from_layers<E>(
+        file: Option<(&str, &str)>,
+        env: E,
+        overrides: &[String],
+    ) -> Result<Self, ConfigError>
+    where
+        E: IntoIterator<Item = (String, String)>,
+    {
+        let mut tree = Value::Table(Table::new());
+        if let Some((name, contents)) = file {
+            let layer: Value = toml::from_str(contents).map_err(|e| ConfigError::Parse {
+                origin: name.to_string(),
+                message: e.to_string(),
+            })?;
+            merge(&mut tree, layer);
+        }
+        let mut env: Vec<(String, String)> = env.into_iter().collect();
+        // The legacy variable goes first so that its successor wins.
+        env.sort_by_key(|(name, _)| name != LEGACY_DATABASE_URL);
+        for (name, value) in env {
+            if name == LEGACY_DATABASE_URL {
+                set(&mut tree, "storage.url", &value)?;
+            } else if let Some(key) = name.strip_prefix(ENV_PREFIX) {
+                let key = key.to_lowercase();
+                let section = ENV_SECTIONS.iter().find_map(|section| {
+                    key.strip_prefix(section)
+                        .and_then(|x| x.strip_prefix('_'))
+                        .map(|field| format!("{}.{}", section, field))
+                });
+                if let Some(key) = section {
+                    set(&mut tree, &key, &value)?;
+                }
+            }
+        }
+        for x in overrides {
+            let (key, value) = x
+                .split_once('=')
+                .ok_or_else(|| ConfigError::Malformed(x.clone()))?;
+            set(&mut tree, key.trim(), value.trim())?;
+        }
+        let settings: Settings = tree.try_into().map_err(|e| ConfigError::Parse {
+            origin: "settings".to_string(),
+            message: e.to_string(),
+        })?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = vec![];
+        if let Err(e) = self.storage.backend() {
+            problems.push(e);
+        }
+        if self.storage.pool_size == 0 {
+            problems.push("storage.pool_size must be at least 1".to_string());
+        }
+        if let Err(e) = self.http.bind() {
+            problems.push(e);
+        }
+        if let Err(e) = self.http.admin_bind() {
+            problems.push(e);
+        }
+        if self.outbox.interval_secs == 0 {
+            problems.push("outbox.interval_secs must be at least 1".to_string());
+        }
+        if self.outbox.batch_size < 1 {
+            problems.push("outbox.batch_size must be at least 1".to_string());
+        }
+        if self.outbox.lease_secs < 1 {
+            problems.push("outbox.lease_secs must be at least 1".to_string());
+        }
+        if self.outbox.max_attempts < 1 {
+            problems.push("outbox.max_attempts must be at least 1".to_string());
+        }
+        if self.outbox.backoff_secs < 1 {
+            problems.push("outbox.backoff_secs must be at least 1".to_string());
+        }
+        if self.outbox.max_backoff_secs < self.outbox.backoff_secs {
+            problems
+                .push("outbox.max_backoff_secs must not be below outbox.backoff_secs".to_string());
+        }
+        let threshold = match self.snapshot.policy {
+            SnapshotPolicyKind::Never => None,
+            SnapshotPolicyKind::EveryNEvents => Some(("events", self.snapshot.events)),
+            SnapshotPolicyKind::Interval => Some(("interval_secs", self.snapshot.interval_secs)),
+            SnapshotPolicyKind::EventBytes => Some(("bytes", self.snapshot.bytes as i64)),
+        };
+        if let Some((field, value)) = threshold {
+            if value < 1 {
+                problems.push(format!("snapshot.{} must be at least 1", field));
+            }
+        }
+        if self.snapshot.keep_last < 1 {
+            problems.push("snapshot.keep_last must be at least 1".to_string());
+        }
+        if matches!(self.snapshot.max_age_secs, Some(x) if x < 1) {
+            problems.push("snapshot.max_age_secs must be at least 1".to_string());
+        }
+        if self.bus.capacity == 0 {
+            problems.push("bus.capacity must be at least 1".to_string());
+        }
+        if self.inbox.retention_secs < 1 {
+            problems.push("inbox.retention_secs must be at least 1".to_string());
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Table(base), Value::Table(layer)) => {
+            for (key, value) in layer {
+                match base.get_mut(&key) {
+                    Some(x) => merge(x, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+/// Sets `section.field` of `tree` to `raw`, read as a TOML value when it is
+/// one (`10`, `true`) and as a string otherwise.
+fn set(tree: &mut Value, key: &str, raw: &str) -> Result<(), ConfigError> {
+    let (section, field) = key
+        .split_once('.')
+        .ok_or_else(|| ConfigError::Malformed(format!("{}={}", key, raw)))?;
+    let value = toml::from_str::<Table>(&format!("value = {}", raw))
+        .ok()
+        .and_then(|mut x| x.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()));
+    let mut layer = Table::new();
+    let mut fields = Table::new();
+    fields.insert(field.to_string(), value);
+    layer.insert(section.to_string(), Value::Table(fields));
+    merge(tree, Value::Table(layer));
+    Ok(())
+}
+
+#[cfg(test)]
+mod config_test {
+    use crate::context::common::application::service::snapshot::SnapshotRetention;
+    use crate::context::common::domain::entity::error::ConfigError;
+
+    use super::{BusKind, Settings, SnapshotPolicyKind, StorageBackend};
+
+    #[test]
+    fn later_layers_override_earlier_ones() {
+        let file = r#"
+            [storage]
+            url = "postgres://localhost/cqrs"
+            pool_size = 3
+
+            [outbox]
+            interval_secs = 30
+        "#;
+        let env = vec![
+            ("COMMAND_OUTBOX_INTERVAL_SECS".to_string(), "5".to_string()),
+            (
+                "DATABASE_URL".to_string(),
+                "sqlite://ignored.db".to_string(),
+            ),
+            ("COMMAND_STORAGE_POOL_SIZE".to_string(), "8".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+            ("COMMAND_VERSION".to_string(), "2".to_string()),
+            ("COMMAND_LOG_LEVEL".to_string(), "debug".to_string()),
+            (
+                "COMMAND_SNAPSHOT_MAX_AGE_SECS".to_string(),
+                "60".to_string(),
+            ),
+        ];
+        let overrides = vec!["storage.pool_size=12".to_string()];
+
+        let settings =
+            Settings::from_layers(Some(("command.toml", file)), env, &overrides).unwrap();
+
+        assert_eq!(settings.storage.backend(), Ok(StorageBackend::Sqlite));
+        assert_eq!(settings.storage.url, "sqlite://ignored.db");
+        assert_eq!(settings.storage.pool_size, 12);
+        assert_eq!(settings.outbox.interval_secs, 5);
+        assert_eq!(settings.outbox.batch_size, 100);
+        assert_eq!(settings.snapshot.policy, SnapshotPolicyKind::EveryNEvents);
+        assert_eq!(settings.bus.kind, BusKind::Broadcast);
+        assert!(matches!(
+            settings.snapshot.retention(),
+            SnapshotRetention::MaxAge(x) if x == chrono::Duration::seconds(60)
+        ));
+    }
+
+    #[test]
+    fn invalid_settings_are_reported_together() {
+        let overrides = vec![
+            "storage.backend=postgres".to_string(),
+            "http.bind=localhost".to_string(),
+            "snapshot.policy=interval".to_string(),
+            "snapshot.interval_secs=0".to_string(),
+        ];
+
+        let error = Settings::from_layers(None, vec![], &overrides).unwrap_err();
+
+        match error {
+            ConfigError::Invalid(x) => assert_eq!(x.len(), 3, "{:?}", x),
+            x => panic!("unexpected error {:?}", x),
+        }
+        let unknown = Settings::from_layers(None, vec![], &["storage.pool=1".to_string()]);
+        assert!(matches!(unknown, Err(ConfigError::Parse { .. })));
+    }
+}
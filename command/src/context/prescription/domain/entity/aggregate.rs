@@ -1,12 +1,7 @@
 use async_trait::async_trait;
-use chrono::Utc;
-use ulid::Ulid;
 
 use crate::context::{
-    common::domain::entity::{
-        aggregate::Aggregate,
-        event::{AggregateSnapshot, DomainEvent},
-    },
+    common::domain::entity::aggregate::Aggregate,
     prescription::{
         application::ports::outbound::prescription::PrescriptionServices,
         domain::machine::{
@@ -17,14 +12,13 @@ use crate::context::{
 
 use super::{command::PrescriptionCommand, error::PrescriptionError, event::PrescriptionEvent};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct PrescriptionAggregate {
     pub id: Option<String>,
     pub patient_id: Option<String>,
     pub medication_id: Option<String>,
     pub address: Option<String>,
     pub last_event: Option<PrescriptionEvent>,
-    pub applied_events: i32,
 }
 
 #[async_trait]
@@ -39,7 +33,7 @@ impl Aggregate for PrescriptionAggregate {
     }
 
     fn aggregate_id(&self) -> Option<String> {
-        return self.id.clone();
+        self.id.clone()
     }
 
     async fn handle(
@@ -58,7 +52,7 @@ impl Aggregate for PrescriptionAggregate {
             },
             None => create_prescription_machine(States::New),
         };
-        let mut context: PrescriptionContext = PrescriptionContext::new(services);
+        let mut context: PrescriptionContext = PrescriptionContext::new(services.as_ref());
         context.set_command(command.clone());
         fsm.decide(&mut context);
         return match context.get_event() {
@@ -70,7 +64,6 @@ impl Aggregate for PrescriptionAggregate {
     }
 
     fn apply(&mut self, event: Self::Event) {
-        self.applied_events += 1;
         match &event {
             PrescriptionEvent::PrescriptionCreated {
                 id,
@@ -91,34 +84,6 @@ impl Aggregate for PrescriptionAggregate {
             }
         }
     }
-
-    fn snapshot(&mut self) -> Option<AggregateSnapshot<Self>> {
-        if self.applied_events >= 10 {
-            let snapshot: AggregateSnapshot<Self> = AggregateSnapshot {
-                aggregate_id: self.aggregate_id().unwrap(),
-                aggregate_type: Self::aggregate_type(),
-                payload: self.clone(),
-                last_sequence: self.last_event.as_ref().unwrap().event_id(),
-                snapshot_id: Ulid::new().to_string(),
-                timestamp: Utc::now(),
-            };
-            return Some(snapshot);
-        }
-        return None;
-    }
-}
-
-impl Default for PrescriptionAggregate {
-    fn default() -> Self {
-        PrescriptionAggregate {
-            id: None,
-            patient_id: None,
-            medication_id: None,
-            address: None,
-            last_event: None,
-            applied_events: 0,
-        }
-    }
 }
 
 #[cfg(test)]
@@ -1,14 +1,16 @@
+use std::fmt;
+
 #[derive(Debug, Clone)]
 pub enum PrescriptionCommand {
     CreatePrescription(CreatePrescriptionCommand),
     UpdatePrescription(UpdatePrescriptionCommand),
 }
 
-impl PrescriptionCommand {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for PrescriptionCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::CreatePrescription { .. } => "CreatePrescription".into(),
-            Self::UpdatePrescription { .. } => "UpdatePrescription".into(),
+            Self::CreatePrescription { .. } => write!(f, "CreatePrescription"),
+            Self::UpdatePrescription { .. } => write!(f, "UpdatePrescription"),
         }
     }
 }
@@ -20,9 +22,9 @@ pub struct CreatePrescriptionCommand {
     pub address: String,
 }
 
-impl Into<PrescriptionCommand> for CreatePrescriptionCommand {
-    fn into(self) -> PrescriptionCommand {
-        PrescriptionCommand::CreatePrescription(self)
+impl From<CreatePrescriptionCommand> for PrescriptionCommand {
+    fn from(val: CreatePrescriptionCommand) -> Self {
+        PrescriptionCommand::CreatePrescription(val)
     }
 }
 
@@ -32,8 +34,8 @@ pub struct UpdatePrescriptionCommand {
     pub address: String,
 }
 
-impl Into<PrescriptionCommand> for UpdatePrescriptionCommand {
-    fn into(self) -> PrescriptionCommand {
-        PrescriptionCommand::UpdatePrescription(self)
+impl From<UpdatePrescriptionCommand> for PrescriptionCommand {
+    fn from(val: UpdatePrescriptionCommand) -> Self {
+        PrescriptionCommand::UpdatePrescription(val)
     }
 }
@@ -1,5 +1,3 @@
-use ulid::Ulid;
-
 use crate::context::common::domain::entity::event::DomainEvent;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -25,7 +23,7 @@ impl DomainEvent for PrescriptionEvent {
         }
     }
     fn event_version(&self) -> String {
-        return "0.0.1".into();
+        "0.0.1".into()
     }
     fn event_id(&self) -> String {
         match self {
@@ -33,4 +31,18 @@ impl DomainEvent for PrescriptionEvent {
             PrescriptionEvent::PrescriptionUpdated { event_id, .. } => event_id.clone(),
         }
     }
+    fn size_hint(&self) -> usize {
+        match self {
+            PrescriptionEvent::PrescriptionCreated {
+                id,
+                patient_id,
+                medication_id,
+                address,
+                event_id,
+            } => id.len() + patient_id.len() + medication_id.len() + address.len() + event_id.len(),
+            PrescriptionEvent::PrescriptionUpdated { address, event_id } => {
+                address.len() + event_id.len()
+            }
+        }
+    }
 }
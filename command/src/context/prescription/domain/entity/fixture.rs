@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+
+use crate::context::common::domain::entity::{aggregate::Aggregate, event::EventEnvelope};
+
+use super::{aggregate::PrescriptionAggregate, event::PrescriptionEvent};
+
+/// Envelope of the `version`th event of prescription `aggregate_id`, with the
+/// sequence `<aggregate_id>-<version>`.
+fn envelope(
+    aggregate_id: &str,
+    version: i64,
+    payload: impl FnOnce(String) -> PrescriptionEvent,
+) -> EventEnvelope<PrescriptionAggregate> {
+    let sequence = format!("{}-{}", aggregate_id, version);
+    EventEnvelope {
+        aggregate_id: aggregate_id.into(),
+        aggregate_type: PrescriptionAggregate::aggregate_type(),
+        sequence: sequence.clone(),
+        version,
+        position: None,
+        payload: payload(sequence),
+        metadata: HashMap::new(),
+        timestamp: Utc::now(),
+    }
+}
+
+/// Prescription `aggregate_id` being created for patient `p` and medication
+/// `m`, delivered to `address`.
+pub fn created(aggregate_id: &str, address: &str) -> EventEnvelope<PrescriptionAggregate> {
+    envelope(aggregate_id, 1, |event_id| {
+        PrescriptionEvent::PrescriptionCreated {
+            id: aggregate_id.into(),
+            patient_id: "p".into(),
+            medication_id: "m".into(),
+            address: address.into(),
+            event_id,
+        }
+    })
+}
+
+/// Prescription `aggregate_id` moving to `address` at `version`.
+pub fn updated(
+    aggregate_id: &str,
+    version: i64,
+    address: &str,
+) -> EventEnvelope<PrescriptionAggregate> {
+    envelope(aggregate_id, version, |event_id| {
+        PrescriptionEvent::PrescriptionUpdated {
+            address: address.into(),
+            event_id,
+        }
+    })
+}
+
+/// The `version`th event of a prescription: its creation, then updates.
+pub fn event(aggregate_id: &str, version: i64) -> EventEnvelope<PrescriptionAggregate> {
+    match version {
+        1 => created(aggregate_id, "a"),
+        x => updated(aggregate_id, x, "a"),
+    }
+}
@@ -2,3 +2,6 @@ pub mod aggregate;
 pub mod command;
 pub mod error;
 pub mod event;
+#[cfg(test)]
+pub mod fixture;
+pub mod view;
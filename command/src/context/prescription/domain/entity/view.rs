@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+/// The denormalised, query side shape of a prescription.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrescriptionView {
+    pub id: String,
+    pub patient_id: String,
+    pub medication_id: String,
+    pub address: String,
+}
@@ -7,25 +7,25 @@ use crate::context::prescription::domain::entity::{
 pub struct PrescriptionContext<'a> {
     command: Option<PrescriptionCommand>,
     event: Option<PrescriptionEvent>,
-    _services: &'a Box<dyn PrescriptionServices + Send + Sync>,
+    _services: &'a (dyn PrescriptionServices + Send + Sync),
 }
 
 impl<'a> PrescriptionContext<'a> {
-    pub fn new(services: &'a Box<dyn PrescriptionServices + Send + Sync>) -> Self {
-        return Self {
+    pub fn new(services: &'a (dyn PrescriptionServices + Send + Sync)) -> Self {
+        Self {
             command: None,
             event: None,
             _services: services,
-        };
+        }
     }
     pub fn get_event(&self) -> &Option<PrescriptionEvent> {
-        return &self.event;
+        &self.event
     }
     pub fn set_event(&mut self, event: PrescriptionEvent) {
         self.event = Some(event);
     }
     pub fn get_command(&self) -> &Option<PrescriptionCommand> {
-        return &self.command;
+        &self.command
     }
     pub fn set_command(&mut self, command: PrescriptionCommand) {
         self.command = Some(command);
@@ -17,9 +17,8 @@ pub fn create_prescription_machine<'a>(initial_state: States) -> PrescriptionMac
             FSMState::new(New).transition(
                 States::Created,
                 |data| {
-                    return data.get_command().is_some()
-                        && data.get_command().as_ref().unwrap().to_string()
-                            == "CreatePrescription";
+                    data.get_command().is_some()
+                        && data.get_command().as_ref().unwrap().to_string() == "CreatePrescription"
                 },
                 vec![],
             ),
@@ -29,12 +28,11 @@ pub fn create_prescription_machine<'a>(initial_state: States) -> PrescriptionMac
             FSMState::new(Created).transition(
                 States::Created,
                 |data| {
-                    return data.get_command().is_some()
-                        && data.get_command().as_ref().unwrap().to_string()
-                            == "UpdatePrescription";
+                    data.get_command().is_some()
+                        && data.get_command().as_ref().unwrap().to_string() == "UpdatePrescription"
                 },
                 vec![],
             ),
         );
-    return fsm;
+    fsm
 }
@@ -18,14 +18,14 @@ impl<'a> State<PrescriptionContext<'a>> for Created {
 
     fn exit(&mut self, context: &mut PrescriptionContext) {
         let command: &PrescriptionCommand = context.get_command().as_ref().unwrap();
-        match command {
-            PrescriptionCommand::UpdatePrescription(UpdatePrescriptionCommand { id, address }) => {
-                context.set_event(PrescriptionEvent::PrescriptionUpdated {
-                    address: address.clone(),
-                    event_id: Ulid::new().to_string(),
-                })
-            }
-            _ => {}
+        if let PrescriptionCommand::UpdatePrescription(UpdatePrescriptionCommand {
+            address, ..
+        }) = command
+        {
+            context.set_event(PrescriptionEvent::PrescriptionUpdated {
+                address: address.clone(),
+                event_id: Ulid::new().to_string(),
+            })
         }
     }
 
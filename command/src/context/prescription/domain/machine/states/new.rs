@@ -16,19 +16,19 @@ impl<'a> State<PrescriptionContext<'a>> for New {
 
     fn exit(&mut self, context: &mut PrescriptionContext) {
         let command: &PrescriptionCommand = context.get_command().as_ref().unwrap();
-        match command {
-            PrescriptionCommand::CreatePrescription(CreatePrescriptionCommand {
-                medication_id,
-                patient_id,
-                address,
-            }) => context.set_event(PrescriptionEvent::PrescriptionCreated {
+        if let PrescriptionCommand::CreatePrescription(CreatePrescriptionCommand {
+            medication_id,
+            patient_id,
+            address,
+        }) = command
+        {
+            context.set_event(PrescriptionEvent::PrescriptionCreated {
                 id: Ulid::new().to_string(),
                 medication_id: medication_id.clone(),
                 patient_id: patient_id.clone(),
                 address: address.clone(),
                 event_id: Ulid::new().to_string(),
-            }),
-            _ => {}
+            })
         }
     }
 
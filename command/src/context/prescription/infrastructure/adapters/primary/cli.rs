@@ -0,0 +1,195 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+use clap::{Parser, Subcommand};
+
+use crate::context::prescription::application::ports::inbound::{
+    inspect_prescription::InspectPrescriptionUseCase, manage_outbox::ManageOutboxUseCase,
+    rebuild_projection::RebuildProjectionUseCase,
+};
+
+#[derive(Parser)]
+#[command(about = "Prescription command and query service")]
+pub struct Cli {
+    /// TOML configuration file [default: command.toml when present]
+    #[arg(long, global = true)]
+    pub config: Option<PathBuf>,
+    /// Override a setting, e.g. `--set http.bind=127.0.0.1:8080`
+    #[arg(long = "set", value_name = "SECTION.KEY=VALUE", global = true)]
+    pub overrides: Vec<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Serve the REST API and run the background workers (the default)
+    Serve,
+    /// Apply pending schema migrations and exit
+    Migrate,
+    /// Print the event stream, latest snapshot and current state of a prescription
+    Inspect {
+        /// Id of the prescription
+        aggregate_id: String,
+    },
+    /// Rebuild projections from the event log; only while `serve` is stopped,
+    /// use the admin endpoint of a running `serve` instead
+    #[command(alias = "rebuild")]
+    Replay {
+        /// Names of the projections, e.g. `prescription_view` [default: all]
+        projections: Vec<String>,
+    },
+    /// List, retry or purge pending outbox rows
+    Outbox {
+        #[command(subcommand)]
+        action: OutboxAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OutboxAction {
+    /// Print pending rows, oldest first
+    List {
+        /// How many rows to print at most
+        #[arg(long, default_value_t = 100)]
+        limit: i64,
+    },
+    /// Send the next batch of pending rows, as the relay of `serve` does
+    Retry,
+    /// Delete pending rows without sending them
+    Purge {
+        /// Event ids of the rows to delete
+        #[arg(required_unless_present = "all")]
+        event_ids: Vec<String>,
+        /// Delete every pending row
+        #[arg(long, conflicts_with = "event_ids")]
+        all: bool,
+    },
+    /// Print events given up on after their last allowed attempt
+    DeadLetters {
+        /// How many rows to print at most
+        #[arg(long, default_value_t = 100)]
+        limit: i64,
+    },
+    /// Put dead-lettered events back into the outbox with fresh attempts
+    Requeue {
+        /// Event ids of the dead letters to requeue
+        #[arg(required_unless_present = "all")]
+        event_ids: Vec<String>,
+        /// Requeue every dead letter
+        #[arg(long, conflicts_with = "event_ids")]
+        all: bool,
+    },
+}
+
+pub async fn inspect(
+    service: &(dyn InspectPrescriptionUseCase + Sync + Send),
+    id: &str,
+) -> Result<(), anyhow::Error> {
+    let inspection = service.inspect_prescription(id).await?;
+    if inspection.events.is_empty() && inspection.snapshot.is_none() {
+        return Err(anyhow!("No such prescription: {}", id));
+    }
+    println!("Events ({}):", inspection.events.len());
+    for event in &inspection.events {
+        println!(
+            "  v{} position={} id={} at {}: {:?}",
+            event.version,
+            event
+                .position
+                .map_or_else(|| "-".to_string(), |x| x.to_string()),
+            event.sequence,
+            event.timestamp,
+            event.payload
+        );
+    }
+    match &inspection.snapshot {
+        Some(x) => println!(
+            "Snapshot: v{} id={} at {}",
+            x.version, x.snapshot_id, x.timestamp
+        ),
+        None => println!("Snapshot: none"),
+    }
+    println!("State at v{}:\n{:#?}", inspection.version, inspection.state);
+    Ok(())
+}
+
+pub async fn replay(
+    rebuilds: &(dyn RebuildProjectionUseCase + Sync + Send),
+    projections: Vec<String>,
+) -> Result<(), anyhow::Error> {
+    let projections = if projections.is_empty() {
+        rebuilds.projections()
+    } else {
+        projections
+    };
+    for projection in projections {
+        let progress = rebuilds.rebuild_projection(&projection).await?;
+        println!(
+            "Rebuilt {} from {} events, up to position {}",
+            projection, progress.events, progress.position
+        );
+    }
+    Ok(())
+}
+
+pub async fn list_outbox(
+    outbox: &(dyn ManageOutboxUseCase + Sync + Send),
+    limit: i64,
+) -> Result<(), anyhow::Error> {
+    let events = outbox.list_outbox(limit).await?;
+    if events.is_empty() {
+        println!("No events in outbox queue");
+    }
+    for event in events {
+        println!(
+            "{} {} v{} at {}: {:?}",
+            event.sequence, event.aggregate_id, event.version, event.timestamp, event.payload
+        );
+    }
+    Ok(())
+}
+
+pub async fn purge_outbox(
+    outbox: &(dyn ManageOutboxUseCase + Sync + Send),
+    event_ids: Vec<String>,
+    all: bool,
+) -> Result<(), anyhow::Error> {
+    let event_ids = if all { None } else { Some(event_ids) };
+    let purged = outbox.purge_outbox(event_ids).await?;
+    println!("Purged {} outbox events", purged);
+    Ok(())
+}
+
+pub async fn list_dead_letters(
+    outbox: &(dyn ManageOutboxUseCase + Sync + Send),
+    limit: i64,
+) -> Result<(), anyhow::Error> {
+    let dead_letters = outbox.list_dead_letters(limit).await?;
+    if dead_letters.is_empty() {
+        println!("No dead-lettered events");
+    }
+    for x in dead_letters {
+        println!(
+            "{} {} v{} after {} attempts at {}: {}",
+            x.event.sequence,
+            x.event.aggregate_id,
+            x.event.version,
+            x.attempts,
+            x.dead_lettered_at,
+            x.last_error
+        );
+    }
+    Ok(())
+}
+
+pub async fn requeue_dead_letters(
+    outbox: &(dyn ManageOutboxUseCase + Sync + Send),
+    event_ids: Vec<String>,
+    all: bool,
+) -> Result<(), anyhow::Error> {
+    let event_ids = if all { None } else { Some(event_ids) };
+    let requeued = outbox.requeue_dead_letters(event_ids).await?;
+    println!("Requeued {} dead-lettered events", requeued);
+    Ok(())
+}
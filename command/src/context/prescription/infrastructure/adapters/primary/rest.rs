@@ -1,19 +1,78 @@
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::{
-    extract::{Extension, Path},
+    extract::{Extension, Path, Query},
     http::StatusCode,
     response::{IntoResponse, Response},
     routing::post,
     Json, Router,
 };
 
+use crate::context::common::domain::entity::error::{EventStoreError, ReadModelError};
 use crate::context::prescription::{
-    application::service::prescription::ServiceTrait,
+    application::{
+        ports::inbound::get_prescription::{GetPrescriptionUseCase, PrescriptionFilter},
+        ports::inbound::rebuild_projection::RebuildProjectionUseCase,
+        service::prescription::ServiceTrait,
+    },
     domain::entity::command::{CreatePrescriptionCommand, UpdatePrescriptionCommand},
-    infrastructure::dtos::transport::http::{RESTPrescriptionMutation, RESTPrescriptionQuery},
+    infrastructure::dtos::transport::http::{
+        RESTPrescriptionListQuery, RESTPrescriptionMutation, RESTPrescriptionQuery,
+    },
 };
 
+/// Page size of `GET /prescription` when no `limit` is given, and its maximum.
+const DEFAULT_PAGE_SIZE: i64 = 20;
+const MAX_PAGE_SIZE: i64 = 100;
+
+type QueryService = Arc<dyn GetPrescriptionUseCase<RESTPrescriptionQuery> + Sync + Send>;
+type RebuildService = Arc<dyn RebuildProjectionUseCase + Sync + Send>;
+
+fn error_response(error: anyhow::Error) -> Response {
+    match error.downcast_ref::<ReadModelError>() {
+        Some(ReadModelError::UnknownProjection(_)) => {
+            return (
+                StatusCode::NOT_FOUND,
+                serde_json::json!({ "errors": [{
+                    "type": "invalid_request_error",
+                    "code": "resource_missing",
+                    "message": error.to_string(),
+                    "param": "name"
+                }]})
+                .to_string(),
+            )
+                .into_response()
+        }
+        Some(ReadModelError::RebuildInProgress(_)) => {
+            return (
+                StatusCode::CONFLICT,
+                serde_json::json!({ "errors": [{
+                    "type": "conflict_error",
+                    "code": "rebuild_in_progress",
+                    "message": error.to_string(),
+                }]})
+                .to_string(),
+            )
+                .into_response()
+        }
+        _ => {}
+    }
+    match error.downcast_ref::<EventStoreError>() {
+        Some(EventStoreError::ConcurrencyConflict { .. }) => (
+            StatusCode::CONFLICT,
+            serde_json::json!({ "errors": [{
+                "type": "conflict_error",
+                "code": "concurrent_modification",
+                "message": error.to_string(),
+            }]})
+            .to_string(),
+        )
+            .into_response(),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+    }
+}
+
 async fn create_prescription(
     service: Extension<Arc<dyn ServiceTrait<RESTPrescriptionQuery> + Sync + Send>>,
     Json(payload): Json<RESTPrescriptionMutation>,
@@ -43,7 +102,7 @@ async fn create_prescription(
                 "param": "address"
         }));
     }
-    if errors.len() > 0 {
+    if !errors.is_empty() {
         return (
             StatusCode::BAD_REQUEST,
             serde_json::json!({ "errors": errors }).to_string(),
@@ -58,7 +117,7 @@ async fn create_prescription(
     let result = service.create_prescription(command, vec![]).await;
     match result {
         Ok(x) => (StatusCode::OK, serde_json::to_string(&x).unwrap()).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+        Err(e) => error_response(e),
     }
 }
 
@@ -92,7 +151,7 @@ async fn update_prescription(
                 "param": "address"
         }));
     }
-    if errors.len() > 0 {
+    if !errors.is_empty() {
         return (
             StatusCode::BAD_REQUEST,
             serde_json::json!({ "errors": errors }).to_string(),
@@ -100,13 +159,113 @@ async fn update_prescription(
             .into_response();
     }
     let command = UpdatePrescriptionCommand {
-        id: id,
+        id,
         address: payload.address.unwrap(),
     };
     let result = service.update_prescription(command, vec![]).await;
     match result {
         Ok(x) => (StatusCode::OK, serde_json::to_string(&x).unwrap()).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+async fn get_prescription(queries: Extension<QueryService>, Path(id): Path<String>) -> Response {
+    match queries.get_prescription(&id).await {
+        Ok(Some(x)) => (StatusCode::OK, serde_json::to_string(&x).unwrap()).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            serde_json::json!({ "errors": [{
+                "type": "invalid_request_error",
+                "code": "resource_missing",
+                "message": format!("No such prescription: {}", id),
+                "param": "id"
+            }]})
+            .to_string(),
+        )
+            .into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+async fn list_prescriptions(
+    queries: Extension<QueryService>,
+    Query(params): Query<RESTPrescriptionListQuery>,
+) -> Response {
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = params.offset.unwrap_or(0);
+    let mut errors = vec![];
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        errors.push(serde_json::json!({
+                "type": "invalid_request_error",
+                "code": "parameter_invalid",
+                "message": format!("limit must be between 1 and {}", MAX_PAGE_SIZE),
+                "param": "limit"
+        }));
+    }
+    if offset < 0 {
+        errors.push(serde_json::json!({
+                "type": "invalid_request_error",
+                "code": "parameter_invalid",
+                "message": "offset must not be negative",
+                "param": "offset"
+        }));
+    }
+    if !errors.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            serde_json::json!({ "errors": errors }).to_string(),
+        )
+            .into_response();
+    }
+    let filter = PrescriptionFilter {
+        patient_id: params.patient_id,
+        medication_id: params.medication_id,
+    };
+    // One extra row tells whether another page follows.
+    let result = queries.list_prescriptions(filter, limit + 1, offset).await;
+    match result {
+        Ok(mut x) => {
+            let has_more = x.len() as i64 > limit;
+            x.truncate(limit as usize);
+            (
+                StatusCode::OK,
+                serde_json::json!({ "data": x, "has_more": has_more }).to_string(),
+            )
+                .into_response()
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+async fn regenerate_snapshots(
+    service: Extension<Arc<dyn ServiceTrait<RESTPrescriptionQuery> + Sync + Send>>,
+) -> Response {
+    match service.regenerate_snapshots().await {
+        Ok(x) => (
+            StatusCode::OK,
+            serde_json::json!({ "regenerated": x }).to_string(),
+        )
+            .into_response(),
+        Err(e) => error_response(e),
+    }
+}
+
+async fn rebuild_projection(
+    rebuilds: Extension<RebuildService>,
+    Path(name): Path<String>,
+) -> Response {
+    match rebuilds.rebuild_projection(&name).await {
+        Ok(x) => (
+            StatusCode::OK,
+            serde_json::json!({
+                "projection": name,
+                "events": x.events,
+                "position": x.position,
+            })
+            .to_string(),
+        )
+            .into_response(),
+        Err(e) => error_response(e),
     }
 }
 
@@ -115,19 +274,183 @@ pub struct RESTPrescriptionAdapter {
 }
 
 impl RESTPrescriptionAdapter {
-    pub fn new(service: Arc<dyn ServiceTrait<RESTPrescriptionQuery> + Sync + Send>) -> Self {
+    pub fn new(
+        service: Arc<dyn ServiceTrait<RESTPrescriptionQuery> + Sync + Send>,
+        queries: QueryService,
+    ) -> Self {
         RESTPrescriptionAdapter {
             router: Router::new()
-                .route("/prescription", post(create_prescription))
-                .route("/prescription/:id", post(update_prescription))
-                .layer(Extension(service.clone())),
+                .route(
+                    "/prescription",
+                    post(create_prescription).get(list_prescriptions),
+                )
+                .route(
+                    "/prescription/:id",
+                    post(update_prescription).get(get_prescription),
+                )
+                .layer(Extension(service))
+                .layer(Extension(queries)),
+        }
+    }
+
+    pub async fn run(self, address: SocketAddr) -> Result<(), anyhow::Error> {
+        serve(self.router, address).await
+    }
+}
+
+/// Maintenance endpoints that replay events, such as rebuilding a read model.
+///
+/// They have no authentication of their own, so they are served on a listener
+/// apart from the public API, which should only be reachable by operators.
+pub struct RESTAdminAdapter {
+    router: axum::Router,
+}
+
+impl RESTAdminAdapter {
+    pub fn new(
+        service: Arc<dyn ServiceTrait<RESTPrescriptionQuery> + Sync + Send>,
+        rebuilds: RebuildService,
+    ) -> Self {
+        RESTAdminAdapter {
+            router: Router::new()
+                .route(
+                    "/admin/prescription/snapshots/regenerate",
+                    post(regenerate_snapshots),
+                )
+                .route(
+                    "/admin/prescription/projections/:name/rebuild",
+                    post(rebuild_projection),
+                )
+                .layer(Extension(service))
+                .layer(Extension(rebuilds)),
+        }
+    }
+
+    pub async fn run(self, address: SocketAddr) -> Result<(), anyhow::Error> {
+        serve(self.router, address).await
+    }
+}
+
+async fn serve(router: Router, address: SocketAddr) -> Result<(), anyhow::Error> {
+    axum::Server::bind(&address)
+        .serve(router.into_make_service())
+        .await
+        .map_err(|e| e.into())
+}
+
+#[cfg(test)]
+mod rest_test {
+    use std::sync::Arc;
+
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use axum::response::Response;
+    use serde_json::Value;
+    use tower::ServiceExt;
+
+    use crate::context::common::application::ports::outbound::read_model::ReadModelRepository;
+    use crate::context::common::domain::entity::error::{EventStoreError, ReadModelError};
+    use crate::context::common::infrastructure::adapters::secondary::storage::memory::{
+        InMemoryEventRepository, InMemoryReadModelStore,
+    };
+    use crate::context::prescription::application::ports::outbound::prescription::MockPrescriptionServices;
+    use crate::context::prescription::application::service::prescription::PrescriptionService;
+    use crate::context::prescription::application::service::query::PrescriptionQueryService;
+    use crate::context::prescription::domain::entity::view::PrescriptionView;
+
+    use super::{error_response, RESTPrescriptionAdapter};
+
+    async fn adapter(count: usize) -> RESTPrescriptionAdapter {
+        let views = InMemoryReadModelStore::<PrescriptionView>::new();
+        for i in 1..=count {
+            let view = PrescriptionView {
+                id: i.to_string(),
+                patient_id: "p".into(),
+                medication_id: "m".into(),
+                address: "a".into(),
+            };
+            views.save(&i.to_string(), 1, view).await.unwrap();
         }
+        RESTPrescriptionAdapter::new(
+            Arc::new(PrescriptionService::new(
+                Box::new(MockPrescriptionServices::new()),
+                InMemoryEventRepository::new(),
+            )),
+            Arc::new(PrescriptionQueryService::new(views)),
+        )
     }
 
-    pub async fn run(self) -> Result<(), anyhow::Error> {
-        axum::Server::bind(&"0.0.0.0:3000".parse().unwrap())
-            .serve(self.router.into_make_service())
-            .await
-            .map_err(|e| e.into())
+    async fn get(adapter: &RESTPrescriptionAdapter, uri: &str) -> (StatusCode, Value) {
+        let request = Request::get(uri).body(Body::empty()).unwrap();
+        read(adapter.router.clone().oneshot(request).await.unwrap()).await
+    }
+
+    async fn read(response: Response) -> (StatusCode, Value) {
+        let status = response.status();
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or_default())
+    }
+
+    fn ids(body: &Value) -> Vec<&str> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x["id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn prescriptions_are_read_by_id_and_in_pages() {
+        let adapter = adapter(3).await;
+
+        let (status, body) = get(&adapter, "/prescription/2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["id"], "2");
+        let (status, body) = get(&adapter, "/prescription/9").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["errors"][0]["code"], "resource_missing");
+
+        let (status, body) = get(&adapter, "/prescription?limit=2").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&body), vec!["1", "2"]);
+        assert_eq!(body["has_more"], true);
+        let (_, body) = get(&adapter, "/prescription?limit=2&offset=2").await;
+        assert_eq!(ids(&body), vec!["3"]);
+        assert_eq!(body["has_more"], false);
+        let (_, body) = get(&adapter, "/prescription?limit=3").await;
+        assert_eq!(body["has_more"], false);
+        let (status, body) = get(&adapter, "/prescription?limit=0&offset=-1").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["errors"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn errors_map_to_status_codes() {
+        let conflict = EventStoreError::ConcurrencyConflict {
+            aggregate_id: "1".into(),
+            expected: 1,
+            actual: 2,
+        };
+        let (status, body) = read(error_response(conflict.into())).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["errors"][0]["code"], "concurrent_modification");
+
+        let (status, body) = read(error_response(
+            ReadModelError::RebuildInProgress("view".into()).into(),
+        ))
+        .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["errors"][0]["code"], "rebuild_in_progress");
+
+        let (status, body) = read(error_response(
+            ReadModelError::UnknownProjection("nope".into()).into(),
+        ))
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["errors"][0]["param"], "name");
+
+        let (status, _) = read(error_response(anyhow::anyhow!("down"))).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
     }
 }
@@ -1 +1,41 @@
-pub mod sqlite;
+use crate::context::{
+    common::infrastructure::{
+        adapters::secondary::storage::{
+            event_store::SqlEventStore, postgres::PostgresConnector, projection::SqlReadModelStore,
+            sqlite::SqliteConnector,
+        },
+        upcaster::UpcasterRegistry,
+    },
+    prescription::{
+        domain::entity::{aggregate::PrescriptionAggregate, view::PrescriptionView},
+        infrastructure::dtos::storage::sql::{SQLPrescriptionAggregate, SQLPrescriptionEvent},
+    },
+};
+
+pub type SqlitePrescriptionRepository = SqlEventStore<
+    SqliteConnector,
+    PrescriptionAggregate,
+    SQLPrescriptionEvent,
+    SQLPrescriptionAggregate,
+>;
+
+pub type PostgresPrescriptionRepository = SqlEventStore<
+    PostgresConnector,
+    PrescriptionAggregate,
+    SQLPrescriptionEvent,
+    SQLPrescriptionAggregate,
+>;
+
+/// The table `PrescriptionView`s are kept in.
+pub const PRESCRIPTION_VIEW_TABLE: &str = "prescription_view";
+
+pub type SqlitePrescriptionViews = SqlReadModelStore<SqliteConnector, PrescriptionView>;
+
+pub type PostgresPrescriptionViews = SqlReadModelStore<PostgresConnector, PrescriptionView>;
+
+/// Upcasters for prescription events stored under an older `event_version`.
+///
+/// Register a step here whenever the shape of a `PrescriptionEvent` changes.
+pub fn upcasters() -> UpcasterRegistry {
+    UpcasterRegistry::new()
+}
@@ -23,20 +23,20 @@ pub enum SQLPrescriptionEvent {
 
 impl Default for SQLPrescriptionEvent {
     fn default() -> Self {
-        return Self::PrescriptionCreated {
+        Self::PrescriptionCreated {
             id: "".into(),
             event_id: Ulid::new().to_string(),
             medication_id: "".into(),
             patient_id: "".into(),
             address: "".into(),
-        };
+        }
     }
 }
 
-impl Into<Option<PrescriptionEvent>> for SQLPrescriptionEvent {
-    fn into(self) -> Option<PrescriptionEvent> {
-        match self {
-            Self::PrescriptionCreated {
+impl From<SQLPrescriptionEvent> for Option<PrescriptionEvent> {
+    fn from(val: SQLPrescriptionEvent) -> Self {
+        match val {
+            SQLPrescriptionEvent::PrescriptionCreated {
                 id,
                 event_id,
                 patient_id,
@@ -49,7 +49,7 @@ impl Into<Option<PrescriptionEvent>> for SQLPrescriptionEvent {
                 medication_id,
                 address,
             }),
-            Self::PrescriptionUpdated { event_id, address } => {
+            SQLPrescriptionEvent::PrescriptionUpdated { event_id, address } => {
                 Some(PrescriptionEvent::PrescriptionUpdated { address, event_id })
             }
         }
@@ -79,10 +79,10 @@ impl From<PrescriptionEvent> for SQLPrescriptionEvent {
     }
 }
 
-impl Into<PrescriptionEvent> for SQLPrescriptionEvent {
-    fn into(self) -> PrescriptionEvent {
-        match self {
-            Self::PrescriptionCreated {
+impl From<SQLPrescriptionEvent> for PrescriptionEvent {
+    fn from(val: SQLPrescriptionEvent) -> Self {
+        match val {
+            SQLPrescriptionEvent::PrescriptionCreated {
                 id,
                 event_id,
                 patient_id,
@@ -95,7 +95,7 @@ impl Into<PrescriptionEvent> for SQLPrescriptionEvent {
                 patient_id,
                 address,
             },
-            Self::PrescriptionUpdated { event_id, address } => {
+            SQLPrescriptionEvent::PrescriptionUpdated { event_id, address } => {
                 PrescriptionEvent::PrescriptionUpdated { address, event_id }
             }
         }
@@ -107,28 +107,66 @@ pub struct SQLPrescriptionAggregate {
     id: Option<String>,
     patient_id: Option<String>,
     medication_id: Option<String>,
+    address: Option<String>,
     last_event: Option<SQLPrescriptionEvent>,
 }
 
-impl Into<PrescriptionAggregate> for SQLPrescriptionAggregate {
-    fn into(self) -> PrescriptionAggregate {
-        return PrescriptionAggregate {
-            id: self.id,
-            patient_id: self.patient_id,
-            medication_id: self.medication_id,
-            last_event: self.last_event.map(|x| x.into()),
-            ..Default::default()
-        };
+impl From<SQLPrescriptionAggregate> for PrescriptionAggregate {
+    fn from(val: SQLPrescriptionAggregate) -> Self {
+        PrescriptionAggregate {
+            id: val.id,
+            patient_id: val.patient_id,
+            medication_id: val.medication_id,
+            address: val.address,
+            last_event: val.last_event.map(|x| x.into()),
+        }
     }
 }
 
 impl From<PrescriptionAggregate> for SQLPrescriptionAggregate {
     fn from(value: PrescriptionAggregate) -> Self {
-        return SQLPrescriptionAggregate {
+        SQLPrescriptionAggregate {
             id: value.id,
             patient_id: value.patient_id,
             medication_id: value.medication_id,
+            address: value.address,
             last_event: value.last_event.map(|x| x.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod sql_test {
+    use crate::context::prescription::domain::entity::{
+        aggregate::PrescriptionAggregate, event::PrescriptionEvent,
+    };
+
+    use super::SQLPrescriptionAggregate;
+
+    #[test]
+    fn snapshots_keep_every_field_of_the_aggregate() {
+        let aggregate = PrescriptionAggregate {
+            id: Some("1".into()),
+            patient_id: Some("p".into()),
+            medication_id: Some("m".into()),
+            address: Some("1 Main St".into()),
+            last_event: Some(PrescriptionEvent::PrescriptionUpdated {
+                address: "1 Main St".into(),
+                event_id: "e".into(),
+            }),
         };
+
+        let stored =
+            serde_json::to_string(&SQLPrescriptionAggregate::from(aggregate.clone())).unwrap();
+        let loaded: PrescriptionAggregate =
+            serde_json::from_str::<SQLPrescriptionAggregate>(&stored)
+                .unwrap()
+                .into();
+
+        assert_eq!(loaded.id, aggregate.id);
+        assert_eq!(loaded.patient_id, aggregate.patient_id);
+        assert_eq!(loaded.medication_id, aggregate.medication_id);
+        assert_eq!(loaded.address, aggregate.address);
+        assert_eq!(loaded.last_event, aggregate.last_event);
     }
 }
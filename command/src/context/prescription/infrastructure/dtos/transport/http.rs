@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
+use crate::context::prescription::domain::entity::{
+    aggregate::PrescriptionAggregate, view::PrescriptionView,
+};
 
 #[derive(Default, Deserialize, Serialize, Debug)]
 pub struct RESTPrescriptionMutation {
@@ -15,11 +17,11 @@ impl RESTPrescriptionMutation {
         medication_id: Option<String>,
         address: Option<String>,
     ) -> Self {
-        return Self {
+        Self {
             patient_id,
             medication_id,
             address,
-        };
+        }
     }
 }
 
@@ -33,11 +35,31 @@ pub struct RESTPrescriptionQuery {
 
 impl From<PrescriptionAggregate> for RESTPrescriptionQuery {
     fn from(value: PrescriptionAggregate) -> Self {
-        return RESTPrescriptionQuery {
+        RESTPrescriptionQuery {
             id: value.id,
             patient_id: value.patient_id,
             medication_id: value.medication_id,
             address: value.address,
-        };
+        }
     }
 }
+
+impl From<PrescriptionView> for RESTPrescriptionQuery {
+    fn from(value: PrescriptionView) -> Self {
+        RESTPrescriptionQuery {
+            id: Some(value.id),
+            patient_id: Some(value.patient_id),
+            medication_id: Some(value.medication_id),
+            address: Some(value.address),
+        }
+    }
+}
+
+/// Query string of `GET /prescription`.
+#[derive(Default, Deserialize, Debug)]
+pub struct RESTPrescriptionListQuery {
+    pub patient_id: Option<String>,
+    pub medication_id: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -3,72 +3,370 @@ pub mod context;
 use std::sync::Arc;
 
 use anyhow::anyhow;
+use chrono::Utc;
+use clap::Parser;
+use context::common::application::ports::outbound::checkpoint_store::CheckpointStore;
 use context::common::application::ports::outbound::event_bus::EventBus;
+use context::common::application::ports::outbound::event_repository::AggregateRepository;
+use context::common::application::ports::outbound::inbox::InboxExpiry;
+use context::common::application::ports::outbound::outbox_signal::OutboxSignal;
+use context::common::application::ports::outbound::read_model::ReadModelRepository;
+use context::common::application::service::projection::ProjectionRunner;
+use context::common::application::service::rebuild::RebuildLock;
 use context::common::domain::entity::event::EventEnvelope;
-use sqlx::{Pool, Sqlite};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::sqlite::SqlitePoolOptions;
 
-use crate::context::common::infrastructure::adapters::secondary::eventbus::channel::ChannelBus;
+use crate::context::common::infrastructure::adapters::secondary::eventbus::broadcast::BroadcastBus;
+use crate::context::common::infrastructure::adapters::secondary::signal::local::LocalOutboxSignal;
+use crate::context::common::infrastructure::adapters::secondary::signal::postgres::PostgresOutboxListener;
+use crate::context::common::infrastructure::adapters::secondary::storage::inbox::SqlInbox;
+use crate::context::common::infrastructure::adapters::secondary::storage::memory::{
+    InMemoryCheckpointStore, InMemoryEventRepository, InMemoryInbox, InMemoryReadModelStore,
+};
+use crate::context::common::infrastructure::adapters::secondary::storage::migrations::AppliedMigration;
+use crate::context::common::infrastructure::adapters::secondary::storage::postgres::PostgresConnector;
+use crate::context::common::infrastructure::adapters::secondary::storage::projection::SqlCheckpointStore;
 use crate::context::common::infrastructure::adapters::secondary::storage::sqlite::SqliteConnector;
-use crate::context::prescription::application::ports::inbound::get_events::GetEvents;
-use crate::context::prescription::application::ports::inbound::send_event::SendEvent;
+use crate::context::prescription::application::ports::inbound::manage_outbox::ManageOutboxUseCase;
+use crate::context::prescription::application::ports::inbound::manage_snapshots::ManageSnapshotsUseCase;
 use crate::context::prescription::application::ports::outbound::prescription::MockPrescriptionServices;
 use crate::context::prescription::application::ports::outbound::prescription::PrescriptionServices;
 use crate::context::prescription::application::service::outbox::PrescriptionOutboxService;
 use crate::context::prescription::application::service::prescription::PrescriptionService;
+use crate::context::prescription::application::service::projection::PrescriptionViewProjection;
+use crate::context::prescription::application::service::query::PrescriptionQueryService;
+use crate::context::prescription::application::service::rebuild::PrescriptionRebuildService;
+use crate::context::prescription::config::{BusKind, Settings, StorageBackend, StorageSettings};
 use crate::context::prescription::domain::entity::aggregate::PrescriptionAggregate;
-use crate::context::prescription::infrastructure::adapters::primary::rest::RESTPrescriptionAdapter;
+use crate::context::prescription::domain::entity::view::PrescriptionView;
+use crate::context::prescription::infrastructure::adapters::primary::cli::{
+    self, Cli, Command, OutboxAction,
+};
+use crate::context::prescription::infrastructure::adapters::primary::rest::{
+    RESTAdminAdapter, RESTPrescriptionAdapter,
+};
+use crate::context::prescription::infrastructure::adapters::secondary::{
+    upcasters, PostgresPrescriptionRepository, PostgresPrescriptionViews,
+    SqlitePrescriptionRepository, SqlitePrescriptionViews, PRESCRIPTION_VIEW_TABLE,
+};
 
 use tokio::signal;
 use tokio::signal::unix::signal;
 use tokio::signal::unix::SignalKind;
 
+/// How often snapshots outside the retention window are pruned.
+const SNAPSHOT_PRUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+/// How often inbox records outside the retention window are expired.
+const INBOX_EXPIRY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+type PrescriptionBus = dyn EventBus<EventEnvelope<PrescriptionAggregate>, EventEnvelope<PrescriptionAggregate>>
+    + Sync
+    + Send;
+
+/// Everything the command and the query side persist to.
+struct Stores {
+    repository: Arc<AggregateRepository<PrescriptionAggregate>>,
+    checkpoints: Arc<dyn CheckpointStore + Sync + Send>,
+    views: Arc<dyn ReadModelRepository<PrescriptionView> + Sync + Send>,
+    inbox: Arc<dyn InboxExpiry + Sync + Send>,
+    // Set when outbox commits are announced to every process over the
+    // database.
+    postgres: Option<Arc<PostgresConnector>>,
+    sqlite: Option<Arc<SqliteConnector>>,
+}
+
+impl Stores {
+    /// Brings the schema of the database up to date and returns the version
+    /// and description of each migration applied.
+    async fn migrate(&self) -> Result<Vec<AppliedMigration>, anyhow::Error> {
+        match (&self.postgres, &self.sqlite) {
+            (Some(x), _) => x.migrate().await,
+            (_, Some(x)) => x.migrate().await,
+            (None, None) => Ok(vec![]),
+        }
+    }
+}
+
+/// Connects to the configured database; the schema is left as it is.
+async fn connect(settings: &StorageSettings) -> Result<Stores, anyhow::Error> {
+    let backend = settings.backend().map_err(|e| anyhow!(e))?;
+    match backend {
+        StorageBackend::Postgres => {
+            let pool = PgPoolOptions::new()
+                .max_connections(settings.pool_size)
+                .connect(&settings.url)
+                .await
+                .map_err(|e| anyhow!(e));
+            let connector = PostgresConnector::new(pool).await?;
+            Ok(Stores {
+                repository: Arc::new(
+                    PostgresPrescriptionRepository::new(connector.clone())
+                        .with_upcasters(upcasters()),
+                ),
+                checkpoints: Arc::new(SqlCheckpointStore::new(connector.clone())),
+                views: Arc::new(PostgresPrescriptionViews::new(
+                    connector.clone(),
+                    PRESCRIPTION_VIEW_TABLE,
+                )),
+                inbox: Arc::new(SqlInbox::new(connector.clone())),
+                postgres: Some(connector),
+                sqlite: None,
+            })
+        }
+        StorageBackend::Sqlite => {
+            let pool = SqlitePoolOptions::new()
+                .max_connections(settings.pool_size)
+                .connect(&settings.url)
+                .await
+                .map_err(|e| anyhow!(e));
+            let connector = SqliteConnector::new(pool).await?;
+            Ok(Stores {
+                repository: Arc::new(
+                    SqlitePrescriptionRepository::new(connector.clone())
+                        .with_upcasters(upcasters()),
+                ),
+                checkpoints: Arc::new(SqlCheckpointStore::new(connector.clone())),
+                views: Arc::new(SqlitePrescriptionViews::new(
+                    connector.clone(),
+                    PRESCRIPTION_VIEW_TABLE,
+                )),
+                inbox: Arc::new(SqlInbox::new(connector.clone())),
+                postgres: None,
+                sqlite: Some(connector),
+            })
+        }
+        StorageBackend::Memory => Ok(Stores {
+            repository: InMemoryEventRepository::new(),
+            checkpoints: InMemoryCheckpointStore::new(),
+            views: InMemoryReadModelStore::new(),
+            inbox: InMemoryInbox::new(),
+            postgres: None,
+            sqlite: None,
+        }),
+    }
+}
+
+/// Relays events from the outbox to the projections.
+struct Pipeline {
+    outbox: Arc<PrescriptionOutboxService>,
+    bus: Arc<PrescriptionBus>,
+    runner: ProjectionRunner<PrescriptionAggregate>,
+}
+
+fn pipeline(settings: &Settings, stores: &Stores) -> Pipeline {
+    let bus: Arc<PrescriptionBus> = match settings.bus.kind {
+        BusKind::Broadcast => Arc::new(BroadcastBus::new().with_capacity(settings.bus.capacity)),
+    };
+    let outbox = Arc::new(
+        PrescriptionOutboxService::new(stores.repository.clone(), bus.clone())
+            .with_batch_size(settings.outbox.batch_size)
+            .with_lease(settings.outbox.lease())
+            .with_retry_policy(settings.outbox.retry_policy()),
+    );
+    let runner = ProjectionRunner::new(stores.checkpoints.clone())
+        .register(Arc::new(PrescriptionViewProjection::new(
+            stores.views.clone(),
+        )))
+        .with_repository(stores.repository.clone());
+    Pipeline {
+        outbox,
+        bus,
+        runner,
+    }
+}
+
+fn prescription_service(settings: &Settings, stores: &Stores) -> PrescriptionService {
+    let services: Box<dyn PrescriptionServices + Sync + Send> =
+        Box::new(MockPrescriptionServices::new());
+    PrescriptionService::new(services, stores.repository.clone())
+        .with_snapshot_policy(settings.snapshot.policy())
+        .with_snapshot_retention(settings.snapshot.retention())
+}
+
+fn rebuild_service(stores: &Stores) -> PrescriptionRebuildService {
+    PrescriptionRebuildService::new(
+        stores.repository.clone(),
+        stores.checkpoints.clone(),
+        stores.views.clone(),
+    )
+}
+
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
+    let cli = Cli::parse();
+    let settings = Settings::load(cli.config.as_deref(), &cli.overrides)?;
+    let stores = connect(&settings.storage).await?;
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => {
+            stores.migrate().await?;
+            serve(settings, stores).await
+        }
+        Command::Migrate => {
+            let applied = stores.migrate().await?;
+            for (version, description) in &applied {
+                println!("Applied migration {:04} {}", version, description);
+            }
+            println!("Schema is up to date");
+            Ok(())
+        }
+        Command::Inspect { aggregate_id } => {
+            cli::inspect(&prescription_service(&settings, &stores), &aggregate_id).await
+        }
+        Command::Replay { projections } => {
+            cli::replay(&rebuild_service(&stores), projections).await
+        }
+        Command::Outbox { action } => outbox(settings, stores, action).await,
+    }
+}
+
+async fn outbox(
+    settings: Settings,
+    stores: Stores,
+    action: OutboxAction,
+) -> Result<(), anyhow::Error> {
+    let pipeline = pipeline(&settings, &stores);
+    match action {
+        OutboxAction::List { limit } => cli::list_outbox(pipeline.outbox.as_ref(), limit).await,
+        OutboxAction::Purge { event_ids, all } => {
+            cli::purge_outbox(pipeline.outbox.as_ref(), event_ids, all).await
+        }
+        OutboxAction::DeadLetters { limit } => {
+            cli::list_dead_letters(pipeline.outbox.as_ref(), limit).await
+        }
+        OutboxAction::Requeue { event_ids, all } => {
+            cli::requeue_dead_letters(pipeline.outbox.as_ref(), event_ids, all).await
+        }
+        OutboxAction::Retry => {
+            let events = pipeline.bus.receive_events().await;
+            let Pipeline {
+                outbox,
+                bus,
+                runner,
+            } = pipeline;
+            // Projections consume while the relay sends, as the bus holds only
+            // so many events, and finish once the bus is dropped.
+            drop(bus);
+            let projecting = tokio::spawn(async move { runner.run(events).await });
+            let report = outbox.relay_outbox().await;
+            drop(outbox);
+            projecting.await?;
+            let report = report?;
+            println!(
+                "Sent {} outbox events, {} more left for redelivery ({} lease lost, {} not \
+                 removed), {} failed ({} dead-lettered), {} skipped",
+                report.sent,
+                report.lease_lost + report.not_deleted,
+                report.lease_lost,
+                report.not_deleted,
+                report.failed,
+                report.dead_lettered,
+                report.skipped
+            );
+            Ok(())
+        }
+    }
+}
+
+async fn serve(settings: Settings, stores: Stores) -> Result<(), anyhow::Error> {
     //TODO: load aggregate from snapshots + events
     //TODO: call handle to generate events
     //TODO: commit events and then dispatch events
-    let conn: Result<Pool<Sqlite>, anyhow::Error> = sqlx::Pool::connect("sqlite://test.db")
-        .await
-        .map_err(|e| anyhow!(e));
-    let connector = SqliteConnector::new(conn).await.unwrap();
-    let services: Box<dyn PrescriptionServices + Sync + Send> =
-        Box::new(MockPrescriptionServices::new());
-    let service: Arc<PrescriptionService> =
-        Arc::new(PrescriptionService::new(services, connector.clone()));
+    let address = settings.http.bind().map_err(|e| anyhow!(e))?;
+    let admin_address = settings.http.admin_bind().map_err(|e| anyhow!(e))?;
+    let outbox_interval = settings.outbox.interval();
+    let outbox_batch_size = settings.outbox.batch_size as usize;
+    let outbox_signal = LocalOutboxSignal::new();
+    let service: Arc<PrescriptionService> = Arc::new(
+        prescription_service(&settings, &stores).with_outbox_signal(outbox_signal.clone()),
+    );
+    if let Some(connector) = stores.postgres.clone() {
+        tokio::spawn(PostgresOutboxListener::new(connector, outbox_signal.clone()).run());
+    }
+
+    let queries: Arc<PrescriptionQueryService> =
+        Arc::new(PrescriptionQueryService::new(stores.views.clone()));
 
-    let eventbus: ChannelBus<EventEnvelope<PrescriptionAggregate>> = ChannelBus::new();
-    let receiver = eventbus.receive_events().await;
+    // Rebuilds pause the projection runner while they swap in a read model.
+    let rebuild_lock = RebuildLock::default();
+    let rebuilds: Arc<PrescriptionRebuildService> =
+        Arc::new(rebuild_service(&stores).with_rebuild_lock(rebuild_lock.clone()));
 
-    let outbox_service: Arc<PrescriptionOutboxService> = Arc::new(PrescriptionOutboxService::new(
-        connector.clone(),
-        Arc::new(eventbus),
-    ));
+    let Pipeline {
+        outbox: outbox_service,
+        bus: eventbus,
+        runner,
+    } = pipeline(&settings, &stores);
+    let runner = runner.with_rebuild_lock(rebuild_lock);
+    // Subscribed before the relay starts, so projections see every event.
+    let events = eventbus.receive_events().await;
+    tokio::spawn(async move { runner.run(events).await });
 
     tokio::spawn(async move {
-        let mut interval = tokio::time::interval(std::time::Duration::from_secs(10));
+        // Commits wake the relay right away; the interval only sweeps up what
+        // a lost wakeup or a retry delay left behind.
+        let mut interval = tokio::time::interval(outbox_interval);
 
         loop {
-            interval.tick().await;
-            match outbox_service.get_events().await {
-                Ok(x) => {
-                    if x.len() == 0 {
-                        println!("No events in outbox queue");
-                    }
-                    for event in x {
-                        match outbox_service.send_event(event).await {
-                            Err(e) => println!("Received error sending event: {:?}", e),
-                            _ => (),
-                        }
-                    }
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = outbox_signal.notified() => {}
+            }
+            // Full batches are drained right away rather than one per wakeup.
+            loop {
+                match outbox_service.relay_outbox().await {
+                    Ok(x) if x.sent == outbox_batch_size => continue,
+                    Ok(_) => {}
+                    Err(e) => println!("Received Error: {:?}", e),
                 }
-                Err(e) => println!("Received Error: {:?}", e),
+                break;
+            }
+        }
+    });
+
+    let pruning_service = service.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(SNAPSHOT_PRUNE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            match pruning_service.prune_snapshots().await {
+                Ok(x) if x > 0 => println!("Pruned {} snapshots", x),
+                Ok(_) => {}
+                Err(e) => println!("Failed to prune snapshots: {:?}", e),
+            }
+        }
+    });
+
+    let inbox = stores.inbox.clone();
+    let inbox_retention = settings.inbox.retention();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(INBOX_EXPIRY_INTERVAL);
+
+        loop {
+            interval.tick().await;
+            match inbox.expire_messages(Utc::now() - inbox_retention).await {
+                Ok(x) if x > 0 => println!("Expired {} inbox messages", x),
+                Ok(_) => {}
+                Err(e) => println!("Failed to expire inbox messages: {:?}", e),
             }
         }
     });
 
+    if let Some(admin_address) = admin_address {
+        let admin = RESTAdminAdapter::new(service.clone(), rebuilds);
+        tokio::spawn(async move {
+            if let Err(e) = admin.run(admin_address).await {
+                println!("Admin REST adapter stopped: {:?}", e);
+            }
+        });
+    }
+
     tokio::spawn(async move {
-        let rest = RESTPrescriptionAdapter::new(service);
-        rest.run().await;
+        let rest = RESTPrescriptionAdapter::new(service, queries);
+        if let Err(e) = rest.run(address).await {
+            println!("REST adapter stopped: {:?}", e);
+        }
     });
 
     let mut sigterm = signal(SignalKind::terminate())?;